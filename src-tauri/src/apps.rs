@@ -1,63 +1,49 @@
+use crate::clicks;
+use crate::error::AlephError;
+use crate::frecency;
+use crate::fuzzy;
 use crate::icons;
+use crate::query;
+use crate::ranking::{Explanation, Page, SortMode};
+use crate::results::{ResultKind, SearchResult};
+use crate::schema::{self, Opened};
+use crate::settings;
+use crate::status::{self, IndexStats, JobGuard, JobKind};
+use crate::tokenizer;
 use dirs;
 use jwalk::rayon::iter::{ParallelBridge, ParallelIterator};
 use jwalk::{Parallelism, WalkDir};
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::{Mutex, OnceLock};
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
 use std::time::{Duration, Instant};
 use tantivy::collector::TopDocs;
-use tantivy::query::{BooleanQuery, Occur, QueryParser, RegexQuery};
+use tantivy::query::{BooleanQuery, BoostQuery, Occur, Query, QueryParser, TermQuery};
 use tantivy::schema::*;
-use tantivy::{doc, Index, IndexWriter, Term};
-use tantivy::TantivyError;
+use tantivy::{doc, Index, IndexReader, ReloadPolicy, TantivyError, Term};
 use tokio;
 use futures::{
     channel::mpsc::{channel, Receiver},
     SinkExt, StreamExt,
 };
 use notify::{Config, Event, RecommendedWatcher, RecursiveMode, Watcher};
-use notify::event::{EventKind, CreateKind};
+use notify::event::{EventKind, ModifyKind};
 
-pub async fn create_app_launcher() -> Result<(), String> {
-    //El index se va a guardar en ~/.cache/aleph/index
-    //Si no existe el path se crea
-    let home = dirs::home_dir().unwrap();
-    let idx_path = home.join(".cache/aleph/apps");
-    let idx_path = idx_path.as_path();
-
-    if !idx_path.exists() {
-        fs::create_dir_all(idx_path).map_err(|e| e.to_string())?;
-    }
-
-    let mut schema_builder = Schema::builder();
-
-    schema_builder.add_text_field("path", STORED | STRING);
-    schema_builder.add_text_field("filename", TEXT | STORED);
-    schema_builder.add_text_field("extension", STRING | STORED);
-
-    let schema = schema_builder.build();
+use crate::renames::{RenameAction, RenameTracker, PAIR_WINDOW};
+use crate::writer::{Change, WriterHandle};
 
-    //  Abrir o crear el índice de forma segura
-    let index: Index = match Index::create_in_dir(idx_path, schema.clone()) {
-        Ok(idx) => idx, // creado de cero
-        Err(TantivyError::IndexAlreadyExists) => {
-            Index::open_in_dir(idx_path).map_err(|e| e.to_string())?
-        } // ya existía
-        Err(e) => return Err(e.to_string()),
-    };
-
-    let mut index_writer: IndexWriter = index
-        .writer_with_num_threads(10, 200_000_000)
-        .map_err(|e| e.to_string())?;
-    //B: let writer = Arc::new(index.writer(50_000_000)?);
-
-    //voy mandando el indeice de a partes para que se pueda busar aunque el indice no este completo
+pub async fn create_app_launcher() -> Result<(), String> {
+    let apps = apps_index()?;
+    let job = status::start_job(JobKind::Index, schema::APPS.name);
+    let result = fill_apps_index(&apps, &job).await;
+    job.finish(&result);
+    result
+}
 
-    let path_f = schema.get_field("path").unwrap();
-    let filename = schema.get_field("filename").unwrap();
-    let ext_f = schema.get_field("extension").unwrap();
+async fn fill_apps_index(apps: &AppsIndex, job: &JobGuard) -> Result<(), String> {
+    //El index se va a guardar en ~/.cache/aleph/apps
+    let writer = &apps.writer;
 
     //Vamos a indexar todo
     let root_dir = Path::new("/Applications");
@@ -71,6 +57,7 @@ pub async fn create_app_launcher() -> Result<(), String> {
         .par_bridge()
         .for_each(|res| {
             if let Ok(entry) = res {
+                job.scanned(&entry.path(), false);
                 //Filtro si es un .app
                 if entry
                     .path()
@@ -89,20 +76,18 @@ pub async fn create_app_launcher() -> Result<(), String> {
                         .unwrap_or("")
                         .to_lowercase();
                     let doc = doc!(
-                        path_f => path,
-                        filename => name.as_ref(),
-                        ext_f => ext.as_str(),
+                        apps.path_f => path,
+                        apps.filename_f => name.as_ref(),
+                        apps.ext_f => ext.as_str(),
                     );
-                    let _ = index_writer.add_document(doc);
+                    let doc = apps.with_ngram(doc, &name);
+                    if writer.add(doc).is_ok() {
+                        job.indexed(1);
+                    }
                 }
             }
         });
-    index_writer.commit().map_err(|e| e.to_string())?;
-    index_writer
-        .wait_merging_threads()
-        .map_err(|e| e.to_string())?;
-
-    Ok(())
+    writer.commit().await
 }
 
 // watcher helpers (async) para `/Applications`
@@ -132,17 +117,45 @@ async fn async_watch_apps<P: AsRef<Path>>(path: P) -> notify::Result<()> {
     static DEDUP_CACHE: OnceLock<Mutex<std::collections::HashMap<String, Instant>>> = OnceLock::new();
     const DEDUP_TTL: Duration = Duration::from_millis(700);
 
-    while let Some(res) = rx.next().await {
+    let mut renames = RenameTracker::default();
+
+    loop {
+        // Con un rename a medias se espera la otra mitad un rato
+        let next = if renames.has_pending() {
+            match tokio::time::timeout(PAIR_WINDOW, rx.next()).await {
+                Ok(next) => next,
+                Err(_) => {
+                    if let Some(action) = renames.flush() {
+                        apply_app_rename(action);
+                    }
+                    continue;
+                }
+            }
+        } else {
+            rx.next().await
+        };
+        let res = match next {
+            Some(res) => res,
+            None => break,
+        };
         match res {
             Ok(event) => {
-                if event.kind.is_create() || event.kind.is_remove() {
+                if let EventKind::Modify(ModifyKind::Name(mode)) = event.kind {
+                    for action in renames.push(mode, &event) {
+                        apply_app_rename(action);
+                    }
+                } else if event.kind.is_create() || event.kind.is_remove() || event.kind.is_modify() {
                     for changed_path in event.paths {
                         // Resolver el bundle `.app` asociado (si aplica)
                         if let Some(bundle_path) = resolve_app_bundle(&changed_path) {
                             let key = format!(
                                 "{}|{}",
                                 bundle_path.display(),
-                                if event.kind.is_create() { "create" } else { "remove" }
+                                match event.kind {
+                                    EventKind::Create(_) => "create",
+                                    EventKind::Remove(_) => "remove",
+                                    _ => "modify",
+                                }
                             );
 
                             let cache = DEDUP_CACHE.get_or_init(|| Mutex::new(std::collections::HashMap::new()));
@@ -154,13 +167,20 @@ async fn async_watch_apps<P: AsRef<Path>>(path: P) -> notify::Result<()> {
                             map.insert(key, now);
 
                             match &event.kind {
-                                EventKind::Create(CreateKind::File) | EventKind::Create(CreateKind::Folder) | EventKind::Create(CreateKind::Any) => {
+                                // Un cambio adentro del bundle (p. ej. una actualización) lo reindexa
+                                EventKind::Create(_) | EventKind::Modify(_) => {
                                     if let Err(e) = add_app_to_index(&bundle_path) {
                                         println!("Error adding app to index: {}", e);
                                     }
                                 }
                                 EventKind::Remove(_) => {
-                                    if let Err(e) = delete_app_from_index(&bundle_path) {
+                                    // Si se borró algo de adentro, el bundle sigue estando
+                                    let result = if bundle_path.exists() {
+                                        add_app_to_index(&bundle_path)
+                                    } else {
+                                        delete_app_from_index(&bundle_path)
+                                    };
+                                    if let Err(e) = result {
                                         println!("Error deleting app from index: {}", e);
                                     }
                                 }
@@ -192,130 +212,296 @@ fn resolve_app_bundle(path: &Path) -> Option<PathBuf> {
     None
 }
 
-fn open_or_create_apps_index() -> Result<(Index, Field, Field, Field), String> {
-    let home = dirs::home_dir().ok_or_else(|| "no home dir".to_string())?;
-    let idx_dir = home.join(".cache/aleph/apps");
-    if !idx_dir.exists() {
-        fs::create_dir_all(&idx_dir).map_err(|e| e.to_string())?;
+struct AppsIndex {
+    index: Index,
+    reader: IndexReader,
+    writer: WriterHandle,
+    path_f: Field,
+    filename_f: Field,
+    // Falta en índices de versiones anteriores
+    filename_ngram_f: Option<Field>,
+    ext_f: Field,
+    // Recién creado: hay que llenarlo antes de la primera búsqueda
+    empty: AtomicBool,
+    // Se está llenando por primera vez
+    filling: AtomicBool,
+    dir: PathBuf,
+}
+
+impl AppsIndex {
+    fn open(index: Index, dir: PathBuf, empty: bool) -> Result<Self, String> {
+        let s = index.schema();
+        let field = |name: &str| {
+            s.get_field(name)
+                .map_err(|_| format!("field {} not found", name))
+        };
+        let path_f = field("path")?;
+        let filename_f = field("filename")?;
+        let filename_ngram_f = s.get_field("filename_ngram").ok();
+        let ext_f = field("extension")?;
+        let reader = index
+            .reader_builder()
+            .reload_policy(ReloadPolicy::OnCommitWithDelay)
+            .try_into()
+            .map_err(|e: TantivyError| e.to_string())?;
+        let writer = WriterHandle::spawn(&index, "apps")?;
+        Ok(AppsIndex {
+            index,
+            reader,
+            writer,
+            path_f,
+            filename_f,
+            filename_ngram_f,
+            ext_f,
+            empty: AtomicBool::new(empty),
+            filling: AtomicBool::new(false),
+            dir,
+        })
+    }
+
+    fn with_ngram(&self, mut doc: TantivyDocument, name: &str) -> TantivyDocument {
+        if let Some(field) = self.filename_ngram_f {
+            doc.add_text(field, name);
+        }
+        doc
     }
 
-    let mut schema_builder = Schema::builder();
-    let _ = schema_builder.add_text_field("path", STORED | STRING);
-    let _ = schema_builder.add_text_field("filename", TEXT | STORED);
-    let _ = schema_builder.add_text_field("extension", STRING | STORED);
-    let schema = schema_builder.build();
+    fn readable(schema: &Schema) -> bool {
+        ["path", "filename", "extension"]
+            .iter()
+            .all(|name| schema.get_field(name).is_ok())
+    }
+}
 
-    let index = match Index::open_in_dir(&idx_dir) {
-        Ok(idx) => idx,
-        Err(_) => Index::create_in_dir(&idx_dir, schema).map_err(|e| e.to_string())?,
+fn current_apps_index() -> &'static RwLock<Option<Arc<AppsIndex>>> {
+    static CURRENT: OnceLock<RwLock<Option<Arc<AppsIndex>>>> = OnceLock::new();
+    CURRENT.get_or_init(|| RwLock::new(None))
+}
+
+// El índice de apps se abre una sola vez, con su writer de larga vida. Si el de disco
+// es de otra versión del schema se sigue usando mientras se arma el nuevo. Si no se
+// puede abrir, el índice no está listo para buscar
+fn apps_index() -> Result<Arc<AppsIndex>, AlephError> {
+    if let Some(apps) = current_apps_index().read().unwrap().as_ref() {
+        return Ok(apps.clone());
+    }
+    let mut current = current_apps_index().write().unwrap();
+    if let Some(apps) = current.as_ref() {
+        return Ok(apps.clone());
+    }
+
+    let home = dirs::home_dir().ok_or(AlephError::NoHomeDir)?;
+    let idx_dir = home.join(".cache/aleph/apps");
+    let opened =
+        schema::open(&schema::APPS, &idx_dir, AppsIndex::readable)?;
+    let apps = match opened {
+        Opened::Created(index) => AppsIndex::open(index, idx_dir, true)?,
+        Opened::Current(index) => AppsIndex::open(index, idx_dir, false)?,
+        Opened::Migrate {
+            old,
+            fresh,
+            fresh_dir,
+        } => {
+            let old = AppsIndex::open(old, idx_dir, false)?;
+            let fresh = AppsIndex::open(fresh, fresh_dir, false)?;
+            tokio::spawn(migrate_apps_index(fresh));
+            old
+        }
     };
+    let apps = Arc::new(apps);
+    *current = Some(apps.clone());
+    Ok(apps)
+}
 
-    let s = index.schema();
-    let path_f = s.get_field("path").map_err(|_| "field path not found".to_string())?;
-    let filename_f = s.get_field("filename").map_err(|_| "field filename not found".to_string())?;
-    let ext_f = s.get_field("extension").map_err(|_| "field extension not found".to_string())?;
-    Ok((index, path_f, filename_f, ext_f))
+pub fn stats() -> Result<IndexStats, AlephError> {
+    let apps = apps_index()?;
+    Ok(status::index_stats(&schema::APPS, &apps.reader, &apps.dir))
+}
+
+// Arma el índice nuevo de apps y lo pone en uso cuando termina
+async fn migrate_apps_index(fresh: AppsIndex) {
+    let job = status::start_job(JobKind::Migrate, schema::APPS.name);
+    let result = fill_apps_index(&fresh, &job).await;
+    job.finish(&result);
+    if let Err(e) = result {
+        println!("Error migrating apps index: {}", e);
+        return;
+    }
+    if let Err(e) = schema::finish(&schema::APPS, &fresh.dir) {
+        println!("Error finishing apps index migration: {}", e);
+        return;
+    }
+    *current_apps_index().write().unwrap() = Some(Arc::new(fresh));
 }
 
 fn add_app_to_index(bundle_path: &Path) -> Result<(), String> {
-    if bundle_path.extension().and_then(|s| s.to_str()).unwrap_or("") != "app" {
+    move_app_in_index(None, Some(bundle_path))
+}
+
+// Rename/move de un bundle en un solo commit. Si el bundle ya estaba en el índice
+// (evento repetido o modificación) se reemplaza
+fn move_app_in_index(from: Option<&Path>, to: Option<&Path>) -> Result<(), String> {
+    let is_app = |p: &Path| p.extension().and_then(|s| s.to_str()).unwrap_or("") == "app";
+    let from = from.filter(|p| is_app(p));
+    let to = to.filter(|p| is_app(p) && p.exists());
+    if from.is_none() && to.is_none() {
         return Ok(());
     }
-    let (index, path_f, filename_f, ext_f) = open_or_create_apps_index()?;
-    let mut writer: IndexWriter = index
-        .writer_with_num_threads(2, 50_000_000)
-        .map_err(|e| e.to_string())?;
 
-    let path_str = bundle_path.display().to_string();
-    let name = bundle_path
-        .file_name()
-        .and_then(|s| s.to_str())
-        .unwrap_or("");
-    let ext = "app";
-
-    let document = doc!(
-        path_f => path_str,
-        filename_f => name,
-        ext_f => ext,
-    );
-    writer.add_document(document).map_err(|e| e.to_string())?;
-    writer.commit().map_err(|e| e.to_string())?;
-    writer.wait_merging_threads().map_err(|e| e.to_string())?;
-    Ok(())
+    let apps = apps_index()?;
+    let mut changes = Vec::new();
+    if let Some(from) = from {
+        changes.push(Change::Delete(Term::from_field_text(
+            apps.path_f,
+            &from.display().to_string(),
+        )));
+    }
+    if let Some(to) = to {
+        let path_str = to.display().to_string();
+        let name = to
+            .file_name()
+            .and_then(|s| s.to_str())
+            .unwrap_or("");
+        let document = doc!(
+            apps.path_f => path_str.as_str(),
+            apps.filename_f => name,
+            apps.ext_f => "app",
+        );
+        let document = apps.with_ngram(document, name);
+        changes.push(Change::Upsert(
+            Term::from_field_text(apps.path_f, &path_str),
+            document,
+        ));
+    }
+    apps.writer.apply(changes)
+}
+
+fn apply_app_rename(action: RenameAction) {
+    // Un rename adentro de un bundle no cambia el bundle: se resuelve cada lado a su .app
+    let result = match &action {
+        RenameAction::Moved { from, to } => {
+            let from_bundle = resolve_app_bundle(from);
+            let to_bundle = resolve_app_bundle(to);
+            if from_bundle == to_bundle {
+                Ok(())
+            } else {
+                move_app_in_index(from_bundle.as_deref(), to_bundle.as_deref())
+            }
+        }
+        RenameAction::Removed(from) => match resolve_app_bundle(from) {
+            Some(bundle) if bundle.as_path() == from.as_path() => delete_app_from_index(&bundle),
+            _ => Ok(()),
+        },
+        RenameAction::Added(to) => match resolve_app_bundle(to) {
+            Some(bundle) => add_app_to_index(&bundle),
+            None => Ok(()),
+        },
+    };
+    if let Err(e) = result {
+        println!("Error applying app rename {:?}: {}", action, e);
+    }
 }
 
 fn delete_app_from_index(bundle_path: &Path) -> Result<(), String> {
     if bundle_path.extension().and_then(|s| s.to_str()).unwrap_or("") != "app" {
         return Ok(());
     }
-    let (index, path_f, _filename_f, _ext_f) = open_or_create_apps_index()?;
-    let mut writer: IndexWriter = index
-        .writer_with_num_threads(2, 50_000_000)
-        .map_err(|e| e.to_string())?;
-
+    let apps = apps_index()?;
     let path_str = bundle_path.display().to_string();
-    let term = Term::from_field_text(path_f, &path_str);
-    writer.delete_term(term);
-    writer.commit().map_err(|e| e.to_string())?;
-    writer.wait_merging_threads().map_err(|e| e.to_string())?;
-    Ok(())
+    apps.writer.delete(Term::from_field_text(apps.path_f, &path_str))
 }
 
 #[tauri::command]
-pub async fn app_search(query: &str) -> Result<Vec<(String, String, Option<String>)>, String> {
-    //Deberia chequear si se creo el index
-    let home = dirs::home_dir().unwrap();
-    let idx_path = home.join(".cache/aleph/apps");
-    let idx_path = idx_path.as_path();
-
-    if !idx_path.exists() {
-        fs::create_dir_all(idx_path).map_err(|e| e.to_string())?;
-    }
-
-    //  Abrir o crear el índice de forma segura
-    let index = match Index::open_in_dir(idx_path) {
-        Ok(idx) => idx, // lo encuentra, lo abre
-        Err(_) => {
-            // Crear el índice y luego abrirlo
-            create_app_launcher().await?;
-            Index::open_in_dir(idx_path).map_err(|e| e.to_string())?
-        }
-    };
-
-    let reader = index.reader().map_err(|e| e.to_string())?;
-    reader.reload().map_err(|e| e.to_string())?;
-
-    let schema = index.schema();
+pub async fn app_search(
+    query: &str,
+    limit: Option<usize>,
+    offset: Option<usize>,
+    sort: Option<SortMode>,
+) -> Result<Vec<SearchResult>, AlephError> {
+    rank_apps(query, Page::new(limit, offset, sort)?, false).await
+}
 
-    let path_f = schema.get_field("path").unwrap();
-    let filename = schema.get_field("filename").unwrap();
-    let ext_f = schema.get_field("extension").unwrap();
+// Igual que app_search, con el desglose del puntaje de cada app
+#[tauri::command]
+pub async fn explain_app_search(
+    query: &str,
+    limit: Option<usize>,
+    offset: Option<usize>,
+    sort: Option<SortMode>,
+) -> Result<Vec<SearchResult>, AlephError> {
+    rank_apps(query, Page::new(limit, offset, sort)?, true).await
+}
 
-    let searcher = reader.searcher();
+// La página pedida de apps. Son pocas: se traen todas las que matchean y se ordenan
+// en memoria, así la página no cambia los candidatos (ni el orden entre páginas)
+pub async fn rank_apps(query: &str, page: Page, explain: bool) -> Result<Vec<SearchResult>, AlephError> {
+    let apps = apps_index()?;
+    // La primera vez se llena antes de buscar; las búsquedas que lleguen mientras tanto
+    // no tienen nada que devolver todavía. Si falla, la próxima búsqueda lo reintenta
+    if apps.empty.load(Ordering::SeqCst) {
+        if apps.filling.swap(true, Ordering::SeqCst) {
+            return Err(AlephError::IndexNotReady("las apps se están indexando".to_string()));
+        }
+        let filled = create_app_launcher().await;
+        if filled.is_ok() {
+            apps.empty.store(false, Ordering::SeqCst);
+        }
+        apps.filling.store(false, Ordering::SeqCst);
+        filled?;
+        apps.reader.reload()?;
+    }
 
-    let mut query_parser = QueryParser::for_index(&index, vec![path_f, filename, ext_f]);
-    query_parser.set_field_fuzzy(filename, false, 2, true);
+    // Como en archivos, se busca solo por el texto libre: los operadores (ext:, in:,
+    // ...) no aplican a apps, y nada de la sintaxis del QueryParser hace fallar
+    let parsed = query::parse(query);
+    let text = parsed.text();
+    if text.trim().is_empty() {
+        return Ok(Vec::new());
+    }
 
-    // Fuzzy por nombre + substring case-insensitive por path
-    let fuzzy_query = query_parser.parse_query(query).map_err(|e| e.to_string())?;
-    let escaped = regex::escape(query);
-    let ci_regex = format!("(?i).*{}.*", escaped);
-    let substring_query = RegexQuery::from_pattern(&ci_regex, path_f).map_err(|e| e.to_string())?;
-    let combined = BooleanQuery::new(vec![
-        (Occur::Should, Box::new(fuzzy_query)),
-        (Occur::Should, Box::new(substring_query)),
-    ]);
+    let index = &apps.index;
+    let path_f = apps.path_f;
+    let filename = apps.filename_f;
+    let ext_f = apps.ext_f;
 
-    let top_docs = searcher
-        .search(&combined, &TopDocs::with_limit(15))
-        .map_err(|e| e.to_string())?;
+    let searcher = apps.reader.searcher();
 
-    let mut top_docs_vec: Vec<(String, String, Option<String>)> =
-        Vec::with_capacity(top_docs.len());
+    let mut query_parser = QueryParser::for_index(index, vec![path_f, filename, ext_f]);
+    if !tokenizer::has_cjk(&text) {
+        query_parser.set_field_fuzzy(filename, false, 2, true);
+    }
 
-    for (_score, doc_address) in top_docs {
-        let retrieved_doc: TantivyDocument =
-            searcher.doc(doc_address).map_err(|e| e.to_string())?;
+    // Fuzzy por nombre (partido en palabras, sin escanear paths) + abreviaturas ("vsc")
+    let (fuzzy_query, _) = query_parser.parse_query_lenient(&parsed.parser_input());
+    let mut should: Vec<(Occur, Box<dyn Query>)> = vec![(Occur::Should, fuzzy_query)];
+    if let Some(prefix_query) = apps
+        .filename_ngram_f
+        .and_then(|field| tokenizer::abbreviation_query(field, &text))
+    {
+        should.push((Occur::Should, prefix_query));
+    }
+    // El texto tiene que matchear; las apps que ya se eligieron con esta query solo
+    // suben entre las que matchean
+    let mut clauses: Vec<(Occur, Box<dyn Query>)> =
+        vec![(Occur::Must, Box::new(BooleanQuery::new(should)))];
+    for path in clicks::picked(query) {
+        let term = TermQuery::new(Term::from_field_text(path_f, &path), IndexRecordOption::Basic);
+        let boosted = BoostQuery::new(Box::new(term), settings::settings().ranking.picked_boost);
+        clauses.push((Occur::Should, Box::new(boosted)));
+    }
+    let combined = BooleanQuery::new(clauses);
+
+    let candidates = searcher.num_docs() as usize;
+    let top_docs = searcher.search(&combined, &TopDocs::with_limit(candidates.max(1)))?;
+
+    // Se reordena por qué tan bien matchea el nombre como subsecuencia ("vsc" ->
+    // Visual Studio Code antes que un nombre con las letras desparramadas) y por lo
+    // que más se abre, en general y con esta query
+    let query_len = text.split_whitespace().map(|w| w.chars().count()).sum();
+    let profile = &settings::settings().ranking;
+    let mut ranked: Vec<SearchResult> = Vec::with_capacity(top_docs.len());
+    for (score, doc_address) in top_docs {
+        let retrieved_doc: TantivyDocument = searcher.doc(doc_address)?;
         let name = retrieved_doc
             .get_first(filename)
             .and_then(|v| v.as_str())
@@ -328,11 +514,30 @@ pub async fn app_search(query: &str) -> Result<Vec<(String, String, Option<Strin
             .unwrap_or_default()
             .to_owned();
 
-        // Extract app icon
-        let icon = icons::extract_app_icon(&path);
-
-        top_docs_vec.push((name, path, icon));
+        let name_match = fuzzy::fuzzy_match(&text, &name);
+        let quality = name_match.as_ref().map_or(0.0, |m| m.quality(query_len));
+        let mut explanation = Explanation::new(score);
+        explanation.apply("fuzzy", 1.0 + profile.name_match_weight * quality);
+        explanation.apply("frecency", frecency::boost(&path));
+        explanation.apply("clicks", clicks::boost(query, &path));
+        let score = explanation.score;
+        ranked.push(SearchResult {
+            title_ranges: name_match.map(|m| m.ranges).unwrap_or_default(),
+            explain: explain.then_some(explanation),
+            ..SearchResult::new(ResultKind::App, name, path, score)
+        });
+    }
+    match page.sort {
+        SortMode::Relevance => ranked.sort_by(|a, b| b.score.total_cmp(&a.score)),
+        SortMode::Name => ranked.sort_by_cached_key(|app| tokenizer::fold(&app.title)),
+        SortMode::Modified => ranked.sort_by_cached_key(|app| {
+            std::cmp::Reverse(fs::metadata(&app.path).and_then(|m| m.modified()).ok())
+        }),
+        SortMode::Size => ranked.sort_by_cached_key(|app| {
+            std::cmp::Reverse(fs::metadata(&app.path).map(|m| m.len()).ok())
+        }),
     }
+
     // Start watcher una sola vez
     static STARTED_APPS_WATCHER: AtomicBool = AtomicBool::new(false);
     if !STARTED_APPS_WATCHER.swap(true, Ordering::SeqCst) {
@@ -340,7 +545,12 @@ pub async fn app_search(query: &str) -> Result<Vec<(String, String, Option<Strin
         let _ = tokio::spawn(async move { let _ = async_watch_apps(apps_dir).await; });
     }
 
-    Ok(top_docs_vec)
+    // Los íconos solo de la página que se devuelve
+    let mut page = page.slice(ranked);
+    for app in &mut page {
+        app.icon = icons::extract_app_icon(&app.path);
+    }
+    Ok(page)
 }
 
 #[cfg(test)]
@@ -360,7 +570,7 @@ mod tests {
 
         //creo bien el index, pero encuentra cosas?
         let search = rt.block_on(async {
-            match app_search("Spotify.app").await {
+            match app_search("Spotify.app", None, None, None).await {
                 Ok(top) => top,
                 Err(e) => panic!("Error al buscar: {:?}", e),
             }
@@ -369,7 +579,7 @@ mod tests {
         assert!(!search.is_empty());
 
         assert!(search.iter().any(
-            |(name, path, _icon)| name == "Spotify.app" && path == "/Applications/Spotify.app"
+            |app| app.title == "Spotify.app" && app.path == "/Applications/Spotify.app"
         ));
     }
 }
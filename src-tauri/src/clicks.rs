@@ -0,0 +1,251 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
+
+use crate::frecency::{self, Launch};
+use crate::settings;
+use crate::tokenizer;
+
+// Qué resultado se eligió para cada query, en ~/.config/aleph/clicks.json. Se guarda
+// por cada prefijo de la query: después de abrir invoices-2025.xlsx buscando
+// "invoices" un par de veces, escribir "inv" ya lo pone primero
+const MAX_PREFIX_CHARS: usize = 24;
+// Paths que se recuerdan por prefijo (se olvidan los menos elegidos)
+const MAX_PATHS_PER_PREFIX: usize = 8;
+// Por debajo de esto una elección vieja ya no cuenta y se borra
+const MIN_FRECENCY: f64 = 0.05;
+// Cuántos paths elegidos se traen siempre como candidatos
+pub const PICKED_CANDIDATES: usize = 3;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Table {
+    // Prefijo -> path -> elecciones
+    prefixes: HashMap<String, HashMap<String, Launch>>,
+    // Las queries completas con las que se eligió algo: solo a esas se vuelve desde
+    // una query más larga ("invoices 2025" usa "invoices", pero "image" no usa "i")
+    #[serde(default)]
+    queries: HashSet<String>,
+}
+
+// Sin mayúsculas, acentos ni espacios de más
+fn normalize(query: &str) -> String {
+    tokenizer::fold(query)
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// "inv 25" -> i, in, inv, inv 2, inv 25 (sin los que terminan en espacio). Los
+// prefijos llegan hasta MAX_PREFIX_CHARS, pero la query completa va siempre al final
+fn prefixes(query: &str) -> Vec<&str> {
+    let mut prefixes: Vec<&str> = query
+        .char_indices()
+        .skip(1)
+        .map(|(i, _)| i)
+        .take(MAX_PREFIX_CHARS)
+        .map(|end| &query[..end])
+        .filter(|prefix| !prefix.ends_with(' '))
+        .collect();
+    if !query.is_empty() {
+        prefixes.push(query);
+    }
+    prefixes
+}
+
+fn clicks_path() -> Option<PathBuf> {
+    Some(dirs::home_dir()?.join(".config/aleph/clicks.json"))
+}
+
+fn load_clicks() -> Table {
+    let raw = match clicks_path().and_then(|p| fs::read_to_string(p).ok()) {
+        Some(raw) => raw,
+        None => return Table::default(),
+    };
+    match serde_json::from_str(&raw) {
+        Ok(clicks) => clicks,
+        Err(e) => {
+            println!("clicks.json inválido ({}), empezando de cero", e);
+            Table::default()
+        }
+    }
+}
+
+fn save_clicks(clicks: &Table) -> Result<(), String> {
+    let path = clicks_path().ok_or_else(|| "no home dir".to_string())?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let raw = serde_json::to_string(clicks).map_err(|e| e.to_string())?;
+    fs::write(path, raw).map_err(|e| e.to_string())
+}
+
+// Se carga de disco la primera vez que se usa
+fn clicks() -> &'static Mutex<Table> {
+    static CLICKS: OnceLock<Mutex<Table>> = OnceLock::new();
+    CLICKS.get_or_init(|| Mutex::new(load_clicks()))
+}
+
+fn record_at(clicks: &mut Table, query: &str, path: &str, now: i64) {
+    let query = normalize(query);
+    for prefix in prefixes(&query) {
+        let paths = clicks.prefixes.entry(prefix.to_string()).or_default();
+        paths.entry(path.to_string()).or_default().record(now);
+        paths.retain(|_, pick| pick.frecency(now) >= MIN_FRECENCY);
+        while paths.len() > MAX_PATHS_PER_PREFIX {
+            let least = paths
+                .iter()
+                .min_by(|a, b| a.1.frecency(now).total_cmp(&b.1.frecency(now)))
+                .map(|(p, _)| p.clone());
+            if let Some(least) = least {
+                paths.remove(&least);
+            }
+        }
+    }
+    if !query.is_empty() {
+        clicks.queries.insert(query);
+    }
+}
+
+// Anota que para `query` se eligió `path`
+pub fn record(query: &str, path: &str) -> Result<(), String> {
+    let mut clicks = clicks().lock().unwrap();
+    record_at(&mut clicks, query, path, frecency::unix_now());
+    save_clicks(&clicks)
+}
+
+// Las elecciones de la query tal cual (que puede ser el prefijo de una elegida antes)
+// o, si no hay, las de la query completa más larga que sea prefijo de esta
+fn picks<'a>(clicks: &'a Table, query: &str) -> Option<&'a HashMap<String, Launch>> {
+    let query = normalize(query);
+    clicks.prefixes.get(&query).or_else(|| {
+        clicks
+            .queries
+            .iter()
+            .filter(|complete| query.starts_with(complete.as_str()))
+            .max_by_key(|complete| complete.len())
+            .and_then(|complete| clicks.prefixes.get(complete))
+    })
+}
+
+fn boost_at(clicks: &Table, query: &str, path: &str, now: i64) -> f32 {
+    let Some(picks) = picks(clicks, query) else {
+        return 1.0;
+    };
+    let Some(pick) = picks.get(path) else {
+        return 1.0;
+    };
+    let total: f64 = picks.values().map(|p| p.frecency(now)).sum();
+    let mine = pick.frecency(now);
+    if total <= 0.0 {
+        return 1.0;
+    }
+    // Qué parte de las elecciones se lleva, por cuánta evidencia hay
+    let profile = &settings::settings().ranking;
+    let share = mine / total;
+    let confidence = mine / (mine + profile.click_half_confidence_at);
+    1.0 + profile.click_max_boost * (share * confidence) as f32
+}
+
+// Multiplicador para `path` como resultado de `query`: 1.0 si nunca se eligió, hasta
+// 1.0 + click_max_boost si se elige siempre
+pub fn boost(query: &str, path: &str) -> f32 {
+    let clicks = clicks().lock().unwrap();
+    boost_at(&clicks, query, path, frecency::unix_now())
+}
+
+// Los paths más elegidos para la query, para buscarlos aunque el texto solo no
+// los deje entre los candidatos
+pub fn picked(query: &str) -> Vec<String> {
+    let clicks = clicks().lock().unwrap();
+    let now = frecency::unix_now();
+    let Some(picks) = picks(&clicks, query) else {
+        return Vec::new();
+    };
+    let mut picked: Vec<(&String, f64)> = picks.iter().map(|(p, l)| (p, l.frecency(now))).collect();
+    picked.sort_by(|a, b| b.1.total_cmp(&a.1));
+    picked
+        .into_iter()
+        .take(PICKED_CANDIDATES)
+        .map(|(p, _)| p.clone())
+        .collect()
+}
+
+// Olvida las elecciones de un path, o todas
+pub fn reset(path: Option<&str>) -> Result<(), String> {
+    let mut clicks = clicks().lock().unwrap();
+    match path {
+        Some(path) => {
+            for paths in clicks.prefixes.values_mut() {
+                paths.remove(path);
+            }
+            clicks.prefixes.retain(|_, paths| !paths.is_empty());
+            let Table { prefixes, queries } = &mut *clicks;
+            queries.retain(|query| prefixes.contains_key(query));
+        }
+        None => *clicks = Table::default(),
+    }
+    save_clicks(&clicks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: i64 = 1_700_000_000;
+
+    #[test]
+    fn remembers_picks_by_query_prefix() {
+        let mut clicks = Table::default();
+        let invoices = "/docs/invoices-2025.xlsx";
+        let inventory = "/docs/inventory.ods";
+        record_at(&mut clicks, "Invoices", invoices, NOW);
+        record_at(&mut clicks, "invoices", invoices, NOW);
+        record_at(&mut clicks, "inventory", inventory, NOW);
+
+        // "inv" es prefijo de las dos, pero invoices se eligió más
+        assert!(boost_at(&clicks, "inv", invoices, NOW) > boost_at(&clicks, "inv", inventory, NOW));
+        assert!(boost_at(&clicks, "invo", invoices, NOW) > boost_at(&clicks, "inv", invoices, NOW));
+        assert_eq!(boost_at(&clicks, "invo", inventory, NOW), 1.0);
+        assert_eq!(boost_at(&clicks, "report", invoices, NOW), 1.0);
+        // Un prefijo que no se escribió nunca usa el más largo que sí
+        assert!(boost_at(&clicks, "invoices 2025", invoices, NOW) > 1.0);
+        assert!(boost_at(&clicks, "INVÓ", invoices, NOW) > 1.0);
+    }
+
+    #[test]
+    fn divergent_queries_do_not_reuse_short_prefixes() {
+        let mut clicks = Table::default();
+        let invoices = "/docs/invoices-2025.xlsx";
+        record_at(&mut clicks, "invoices", invoices, NOW);
+
+        // "i" se guardó como prefijo de "invoices", pero "image" no es esa query
+        assert_eq!(boost_at(&clicks, "image", invoices, NOW), 1.0);
+        assert_eq!(boost_at(&clicks, "ical", invoices, NOW), 1.0);
+        assert!(picks(&clicks, "image").is_none());
+        assert!(boost_at(&clicks, "i", invoices, NOW) > 1.0);
+    }
+
+    #[test]
+    fn prefixes_skip_trailing_spaces() {
+        assert_eq!(prefixes("ab c"), vec!["a", "ab", "ab c"]);
+        assert_eq!(normalize("  Año   2025 "), "ano 2025");
+    }
+
+    #[test]
+    fn long_queries_keep_the_complete_query() {
+        let mut clicks = Table::default();
+        let report = "/docs/quarterly-report-2025-final.pdf";
+        let query = "quarterly report 2025 final version";
+        record_at(&mut clicks, query, report, NOW);
+
+        assert_eq!(prefixes(query).last(), Some(&query));
+        assert!(clicks.queries.contains(query));
+        assert!(boost_at(&clicks, query, report, NOW) > 1.0);
+        // Una query que comparte los primeros 24 caracteres no es la elegida
+        assert!(!clicks.queries.contains(&query[..MAX_PREFIX_CHARS]));
+        assert_eq!(boost_at(&clicks, "quarterly report 2025 final draft", report, NOW), 1.0);
+        assert!(boost_at(&clicks, &format!("{} v2", query), report, NOW) > 1.0);
+    }
+}
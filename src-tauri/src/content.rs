@@ -0,0 +1,236 @@
+use chardetng::EncodingDetector;
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::office::{self, OFFICE_EXTENSIONS};
+use crate::settings::{self, ContentSettings};
+
+// Extensiones que se leen como texto plano (documentos, código, configs, datos)
+const TEXT_EXTENSIONS: &[&str] = &[
+    // texto y documentación
+    "txt", "md", "markdown", "rst", "org", "tex", "log", "rtf",
+    // datos y configuración
+    "csv", "tsv", "json", "jsonl", "yaml", "yml", "toml", "ini", "cfg", "conf", "env",
+    "xml", "plist", "properties", "sql",
+    // código
+    "rs", "c", "h", "cpp", "hpp", "cc", "m", "mm", "swift", "go", "py", "rb", "php", "java",
+    "kt", "kts", "scala", "js", "jsx", "ts", "tsx", "mjs", "cjs", "vue", "svelte", "html", "htm",
+    "css", "scss", "sass", "less", "sh", "bash", "zsh", "fish", "ps1", "lua", "pl", "r", "jl",
+    "dart", "ex", "exs", "erl", "hs", "ml", "clj", "cs", "fs", "vb", "zig", "nim", "gradle",
+    "cmake", "make", "dockerfile",
+];
+
+// Archivos sin extensión que también son texto
+const TEXT_FILENAMES: &[&str] = &[
+    "Makefile", "Dockerfile", "README", "LICENSE", "CHANGELOG", "Gemfile", "Rakefile",
+    "Procfile", "Justfile",
+];
+
+pub fn is_text_file(path: &Path, ext: &str) -> bool {
+    if TEXT_EXTENSIONS.contains(&ext) {
+        return true;
+    }
+    ext.is_empty()
+        && path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .map(|n| TEXT_FILENAMES.contains(&n))
+            .unwrap_or(false)
+}
+
+// Lo que se indexa del interior de un archivo además de su nombre
+#[derive(Debug, Default, PartialEq)]
+pub struct Extracted {
+    pub text: String,
+    pub title: Option<String>,
+    pub author: Option<String>,
+}
+
+// Contenido indexable del archivo, o None si no corresponde (deshabilitado, binario,
+// demasiado grande, ilegible o sin extractor). En ese caso se indexa solo por nombre
+pub fn extract(path: &Path, ext: &str) -> Option<Extracted> {
+    let settings = &settings::settings().content;
+    if !settings.enabled {
+        return None;
+    }
+
+    let mut extracted = if ext == "pdf" {
+        Extracted {
+            text: extract_pdf(path, settings)?,
+            ..Default::default()
+        }
+    } else if OFFICE_EXTENSIONS.contains(&ext) {
+        extract_office(path, ext, settings)?
+    } else if is_text_file(path, ext) {
+        Extracted {
+            text: extract_plain(path, settings)?,
+            ..Default::default()
+        }
+    } else {
+        return None;
+    };
+    extracted.text = truncate_to_bytes(extracted.text, settings.max_bytes as usize);
+    Some(extracted)
+}
+
+fn extract_plain(path: &Path, settings: &ContentSettings) -> Option<String> {
+    let meta = fs::metadata(path).ok()?;
+    if meta.len() == 0 || meta.len() > settings.max_bytes {
+        return None;
+    }
+
+    let bytes = fs::read(path).ok()?;
+    decode_text(&bytes)
+}
+
+// Contadores de extracción, para diagnosticar qué documentos quedaron solo con nombre
+static PDF_EXTRACTED: AtomicU64 = AtomicU64::new(0);
+static PDF_FAILED: AtomicU64 = AtomicU64::new(0);
+static OFFICE_EXTRACTED: AtomicU64 = AtomicU64::new(0);
+static OFFICE_FAILED: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ContentDiagnostics {
+    pub pdf_extracted: u64,
+    pub pdf_failed: u64,
+    pub office_extracted: u64,
+    pub office_failed: u64,
+}
+
+pub fn diagnostics() -> ContentDiagnostics {
+    ContentDiagnostics {
+        pdf_extracted: PDF_EXTRACTED.load(Ordering::Relaxed),
+        pdf_failed: PDF_FAILED.load(Ordering::Relaxed),
+        office_extracted: OFFICE_EXTRACTED.load(Ordering::Relaxed),
+        office_failed: OFFICE_FAILED.load(Ordering::Relaxed),
+    }
+}
+
+#[tauri::command]
+pub fn content_diagnostics() -> ContentDiagnostics {
+    diagnostics()
+}
+
+fn extract_pdf(path: &Path, settings: &ContentSettings) -> Option<String> {
+    let meta = fs::metadata(path).ok()?;
+    if meta.len() == 0 || meta.len() > settings.max_document_bytes {
+        return None;
+    }
+    let bytes = fs::read(path).ok()?;
+
+    // pdf-extract puede entrar en pánico con PDFs rotos; un PDF encriptado
+    // (con contraseña) devuelve error. En ambos casos queda indexado solo por nombre
+    let extracted = std::panic::catch_unwind(|| pdf_extract::extract_text_from_mem(&bytes));
+    match extracted {
+        Ok(Ok(text)) if !text.trim().is_empty() => {
+            PDF_EXTRACTED.fetch_add(1, Ordering::Relaxed);
+            Some(text)
+        }
+        _ => {
+            PDF_FAILED.fetch_add(1, Ordering::Relaxed);
+            None
+        }
+    }
+}
+
+fn extract_office(path: &Path, ext: &str, settings: &ContentSettings) -> Option<Extracted> {
+    let meta = fs::metadata(path).ok()?;
+    if meta.len() == 0 || meta.len() > settings.max_document_bytes {
+        return None;
+    }
+
+    match office::extract_office(path, ext, settings.max_document_bytes) {
+        Ok(doc) => {
+            OFFICE_EXTRACTED.fetch_add(1, Ordering::Relaxed);
+            Some(Extracted {
+                text: doc.text,
+                title: doc.title,
+                author: doc.author,
+            })
+        }
+        Err(_) => {
+            OFFICE_FAILED.fetch_add(1, Ordering::Relaxed);
+            None
+        }
+    }
+}
+
+// Corta el texto en un límite de caracteres válido
+fn truncate_to_bytes(mut text: String, max_bytes: usize) -> String {
+    if text.len() > max_bytes {
+        let mut end = max_bytes;
+        while !text.is_char_boundary(end) {
+            end -= 1;
+        }
+        text.truncate(end);
+    }
+    text
+}
+
+// UTF-8 si es válido; si no, se detecta la codificación (latin1, windows-1252, shift_jis...)
+pub fn decode_text(bytes: &[u8]) -> Option<String> {
+    // Un NUL en el primer bloque casi siempre significa binario (o UTF-16, que no soportamos)
+    let head = &bytes[..bytes.len().min(8192)];
+    if head.contains(&0) {
+        return None;
+    }
+
+    let bytes = bytes.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(bytes);
+    if let Ok(text) = std::str::from_utf8(bytes) {
+        return Some(text.to_owned());
+    }
+
+    let mut detector = EncodingDetector::new();
+    detector.feed(bytes, true);
+    let encoding = detector.guess(None, true);
+    let (text, _, had_errors) = encoding.decode(bytes);
+    if had_errors {
+        return None;
+    }
+    Some(text.into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_utf8_and_legacy_encodings() {
+        assert_eq!(decode_text("año 2024".as_bytes()).as_deref(), Some("año 2024"));
+        assert_eq!(decode_text(b"\xEF\xBB\xBFhola").as_deref(), Some("hola"));
+        // "canción" en windows-1252
+        assert_eq!(
+            decode_text(b"canci\xF3n de cuna").as_deref(),
+            Some("canción de cuna")
+        );
+        assert_eq!(decode_text(b"\x7FELF\x00\x01\x02"), None);
+    }
+
+    #[test]
+    fn truncates_on_char_boundary() {
+        assert_eq!(truncate_to_bytes("canción".to_string(), 6), "canci");
+        assert_eq!(truncate_to_bytes("hola".to_string(), 10), "hola");
+    }
+
+    #[test]
+    fn broken_pdf_falls_back_to_name_only() {
+        let dir = std::env::temp_dir().join("aleph-content-test");
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("broken.pdf");
+        fs::write(&path, b"%PDF-1.4\nnot really a pdf").unwrap();
+
+        let before = diagnostics().pdf_failed;
+        assert_eq!(extract(&path, "pdf"), None);
+        assert!(diagnostics().pdf_failed > before);
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn text_file_detection() {
+        assert!(is_text_file(Path::new("/a/notes.md"), "md"));
+        assert!(is_text_file(Path::new("/a/Makefile"), ""));
+        assert!(!is_text_file(Path::new("/a/photo.jpg"), "jpg"));
+    }
+}
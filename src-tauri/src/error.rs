@@ -0,0 +1,118 @@
+use serde::ser::SerializeStruct;
+use serde::{Serialize, Serializer};
+use std::fmt;
+
+// Error de los comandos de Tauri. Al frontend le llega como { code, message }: con el
+// código decide qué mostrar (no es lo mismo un índice que se está armando que una
+// query inválida) y el mensaje queda para el log
+#[derive(Debug, Clone, PartialEq)]
+pub enum AlephError {
+    // El índice todavía se está armando por primera vez y no hay nada para devolver
+    // (si no se puede abrir es Internal)
+    IndexNotReady(String),
+    // La query no se pudo interpretar
+    BadQuery(String),
+    // Un path o una raíz que no existe
+    NotFound(String),
+    // Un argumento que no sirve (p. ej. una raíz que no es un directorio)
+    InvalidInput(String),
+    // Ya hay un trabajo corriendo sobre lo mismo
+    Busy(String),
+    // Una fuente de búsqueda que no respondió a tiempo
+    Timeout(String),
+    NoHomeDir,
+    Io(String),
+    Llm(String),
+    Internal(String),
+}
+
+impl AlephError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            AlephError::IndexNotReady(_) => "index_not_ready",
+            AlephError::BadQuery(_) => "bad_query",
+            AlephError::NotFound(_) => "not_found",
+            AlephError::InvalidInput(_) => "invalid_input",
+            AlephError::Busy(_) => "busy",
+            AlephError::Timeout(_) => "timeout",
+            AlephError::NoHomeDir => "no_home_dir",
+            AlephError::Io(_) => "io",
+            AlephError::Llm(_) => "llm",
+            AlephError::Internal(_) => "internal",
+        }
+    }
+}
+
+impl fmt::Display for AlephError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AlephError::IndexNotReady(e) => write!(f, "index not ready: {}", e),
+            AlephError::BadQuery(e) => write!(f, "bad query: {}", e),
+            AlephError::NotFound(e) => write!(f, "not found: {}", e),
+            AlephError::InvalidInput(e) => write!(f, "invalid input: {}", e),
+            AlephError::Busy(e) => write!(f, "busy: {}", e),
+            AlephError::Timeout(e) => write!(f, "timed out: {}", e),
+            AlephError::NoHomeDir => write!(f, "no home dir"),
+            AlephError::Io(e) | AlephError::Llm(e) | AlephError::Internal(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for AlephError {}
+
+impl Serialize for AlephError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("AlephError", 2)?;
+        state.serialize_field("code", self.code())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.end()
+    }
+}
+
+// Las funciones internas siguen devolviendo String: lo que sube por `?` hasta un
+// comando sin un código más preciso es un error interno
+impl From<String> for AlephError {
+    fn from(e: String) -> Self {
+        AlephError::Internal(e)
+    }
+}
+
+impl From<std::io::Error> for AlephError {
+    fn from(e: std::io::Error) -> Self {
+        AlephError::Io(e.to_string())
+    }
+}
+
+impl From<tantivy::TantivyError> for AlephError {
+    fn from(e: tantivy::TantivyError) -> Self {
+        AlephError::Internal(e.to_string())
+    }
+}
+
+impl From<tantivy::query::QueryParserError> for AlephError {
+    fn from(e: tantivy::query::QueryParserError) -> Self {
+        AlephError::BadQuery(e.to_string())
+    }
+}
+
+impl From<AlephError> for String {
+    fn from(e: AlephError) -> Self {
+        e.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serializes_code_and_message() {
+        let json = serde_json::to_value(AlephError::BadQuery("unexpected ')'".into())).unwrap();
+        assert_eq!(json["code"], "bad_query");
+        assert_eq!(json["message"], "bad query: unexpected ')'");
+        assert_eq!(
+            serde_json::to_value(AlephError::NoHomeDir).unwrap()["code"],
+            "no_home_dir"
+        );
+    }
+}
@@ -0,0 +1,217 @@
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::Match;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+
+// Basura que nunca conviene indexar (dependencias, caches de herramientas).
+// Se puede revertir con `!patrón` en ~/.config/aleph/ignore
+const BUILTIN_EXCLUDES: &[&str] = &[
+    "node_modules/",
+    ".git/",
+    ".svn/",
+    ".hg/",
+    "__pycache__/",
+    ".venv/",
+    ".tox/",
+    ".mypy_cache/",
+    ".pytest_cache/",
+    ".gradle/",
+    ".next/",
+    ".nuxt/",
+    ".turbo/",
+    ".parcel-cache/",
+    "*.pyc",
+    "*.o",
+    "*.class",
+    "*.tmp",
+    "*.swp",
+    "*~",
+    ".DS_Store",
+    "Thumbs.db",
+];
+
+// Salidas de build: una carpeta `build/` o `dist/` cualquiera puede ser del usuario, así
+// que solo se excluyen al lado del archivo del proyecto que las genera. También se
+// pueden revertir con `!patrón`
+const PROJECT_OUTPUTS: &[(&str, &[&str])] = &[
+    ("target", &["Cargo.toml", "pom.xml"]),
+    (
+        "build",
+        &["package.json", "build.gradle", "build.gradle.kts", "CMakeLists.txt", "pyproject.toml", "setup.py"],
+    ),
+    ("dist", &["package.json", "pyproject.toml", "setup.py"]),
+    ("Pods", &["Podfile"]),
+];
+
+fn is_project_output(path: &Path) -> bool {
+    let name = path.file_name().and_then(|n| n.to_str());
+    let (Some(name), Some(parent)) = (name, path.parent()) else {
+        return false;
+    };
+    PROJECT_OUTPUTS
+        .iter()
+        .filter(|(dir, _)| *dir == name)
+        .any(|(_, markers)| markers.iter().any(|marker| parent.join(marker).is_file()))
+}
+
+// Archivos de ignore que se respetan en cada directorio
+const IGNORE_FILES: &[&str] = &[".gitignore", ".ignore"];
+
+fn global_ignore_path() -> Option<PathBuf> {
+    Some(dirs::home_dir()?.join(".config/aleph/ignore"))
+}
+
+// Reglas integradas + archivo global del usuario; se arman una sola vez
+fn global_matcher() -> Arc<Gitignore> {
+    static GLOBAL: OnceLock<Arc<Gitignore>> = OnceLock::new();
+    GLOBAL
+        .get_or_init(|| {
+            let mut builder = GitignoreBuilder::new("/");
+            for pattern in BUILTIN_EXCLUDES {
+                let _ = builder.add_line(None, pattern);
+            }
+            if let Some(path) = global_ignore_path() {
+                if path.exists() {
+                    if let Some(e) = builder.add(&path) {
+                        println!("Error leyendo {:?}: {}", path, e);
+                    }
+                }
+            }
+            Arc::new(builder.build().unwrap_or_else(|_| Gitignore::empty()))
+        })
+        .clone()
+}
+
+// Pila de reglas de ignore: las globales y las de cada directorio desde la raíz
+// hasta el directorio actual. La regla más profunda que matchee decide
+#[derive(Debug, Clone)]
+pub struct IgnoreStack {
+    matchers: Vec<Arc<Gitignore>>,
+}
+
+impl Default for IgnoreStack {
+    fn default() -> Self {
+        IgnoreStack {
+            matchers: vec![global_matcher()],
+        }
+    }
+}
+
+impl IgnoreStack {
+    // Suma los .gitignore/.ignore que haya en `dir`
+    pub fn push_dir(&mut self, dir: &Path) {
+        let present: Vec<PathBuf> = IGNORE_FILES
+            .iter()
+            .map(|name| dir.join(name))
+            .filter(|p| p.is_file())
+            .collect();
+        if present.is_empty() {
+            return;
+        }
+
+        let mut builder = GitignoreBuilder::new(dir);
+        for path in &present {
+            let _ = builder.add(path);
+        }
+        if let Ok(matcher) = builder.build() {
+            self.matchers.push(Arc::new(matcher));
+        }
+    }
+
+    // Reglas vigentes al entrar a `dir`: las de `root` y las de cada carpeta intermedia
+    // (sin las de `dir`, que se suman al leerlo)
+    pub fn above(root: &Path, dir: &Path) -> Self {
+        let mut stack = IgnoreStack::default();
+        let rel = match dir.strip_prefix(root) {
+            Ok(rel) => rel,
+            Err(_) => return stack,
+        };
+        let mut current = root.to_path_buf();
+        for component in rel.components() {
+            stack.push_dir(&current);
+            current.push(component);
+        }
+        stack
+    }
+
+    pub fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        for matcher in self.matchers.iter().rev() {
+            match matcher.matched(path, is_dir) {
+                Match::Ignore(_) => return true,
+                Match::Whitelist(_) => return false,
+                Match::None => {}
+            }
+        }
+        is_dir && is_project_output(path)
+    }
+}
+
+// Para eventos sueltos del watcher: rearma la pila desde la raíz y revisa el path
+// y cada carpeta intermedia (si una carpeta está ignorada, todo lo de adentro también)
+pub fn is_ignored(root: &Path, path: &Path, is_dir: bool) -> bool {
+    let rel = match path.strip_prefix(root) {
+        Ok(rel) => rel,
+        Err(_) => return false,
+    };
+
+    let mut stack = IgnoreStack::default();
+    let mut current = root.to_path_buf();
+    stack.push_dir(&current);
+
+    let components: Vec<_> = rel.components().collect();
+    for (i, component) in components.iter().enumerate() {
+        current.push(component);
+        let last = i + 1 == components.len();
+        if stack.is_ignored(&current, if last { is_dir } else { true }) {
+            return true;
+        }
+        if !last {
+            stack.push_dir(&current);
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn gitignore_and_builtin_rules() {
+        let root = std::env::temp_dir().join("aleph-excludes-test");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("repo/logs")).unwrap();
+        fs::write(root.join("repo/.gitignore"), "*.log\n!keep.log\nlogs/\n").unwrap();
+
+        assert!(is_ignored(&root, &root.join("repo/debug.log"), false));
+        assert!(!is_ignored(&root, &root.join("repo/keep.log"), false));
+        assert!(is_ignored(&root, &root.join("repo/logs/today.txt"), false));
+        assert!(!is_ignored(&root, &root.join("repo/main.rs"), false));
+        assert!(!is_ignored(&root, &root.join("debug.log"), false));
+        assert!(is_ignored(&root, &root.join("web/node_modules/react/index.js"), false));
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn build_outputs_need_a_project_marker() {
+        let root = std::env::temp_dir().join("aleph-outputs-test");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("crate/target")).unwrap();
+        fs::write(root.join("crate/Cargo.toml"), "").unwrap();
+        fs::create_dir_all(root.join("fotos/build")).unwrap();
+        fs::create_dir_all(root.join("docs/dist")).unwrap();
+
+        assert!(is_ignored(&root, &root.join("crate/target"), true));
+        assert!(is_ignored(&root, &root.join("crate/target/debug/app"), false));
+        assert!(!is_ignored(&root, &root.join("fotos/build"), true));
+        assert!(!is_ignored(&root, &root.join("docs/dist/manual.pdf"), false));
+
+        // Una regla del usuario puede volver a incluirlas
+        fs::write(root.join(".ignore"), "!target/\n").unwrap();
+        assert!(!is_ignored(&root, &root.join("crate/target"), true));
+
+        let _ = fs::remove_dir_all(&root);
+    }
+}
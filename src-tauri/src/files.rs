@@ -1,172 +1,752 @@
+use crate::clicks;
+use crate::content;
+use crate::error::AlephError;
+use crate::excludes::{self, IgnoreStack};
+use crate::frecency;
+use crate::fuzzy::{self, FuzzyMatch};
 use crate::icons;
+use crate::query::{self, ParsedQuery};
+use crate::ranking::{Explanation, Page, SortMode};
+use crate::results::{MatchSource, ResultKind, SearchResult};
+use crate::renames::{RenameAction, RenameTracker, PAIR_WINDOW};
+use crate::roots::{self, IndexRoot, RootFilter};
+use crate::schema::{self, Opened};
+use crate::settings;
+use crate::status::{self, IndexStats, JobGuard, JobKind};
+use crate::tokenizer;
+use crate::writer::{Change, WriterHandle};
 use dirs;
-use jwalk::rayon::iter::{ParallelBridge, ParallelIterator};
-use jwalk::{Parallelism, WalkDir};
+use serde::Serialize;
+use jwalk::rayon::iter::{IntoParallelRefIterator, ParallelBridge, ParallelIterator};
+use jwalk::{Parallelism, WalkDirGeneric};
+use std::collections::{HashMap, HashSet};
 use std::fs;
+use std::ops::Bound;
 use std::os::unix::fs::MetadataExt;
 use std::path::{Path, PathBuf};
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::{Mutex, OnceLock};
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
 use std::time::{Duration, Instant};
-use tantivy::collector::TopDocs;
-use tantivy::query::{BooleanQuery, Occur, QueryParser, RegexQuery};
+use tantivy::collector::{Count, DocSetCollector, TopDocs};
+use tantivy::query::{BooleanQuery, BoostQuery, Occur, Query, QueryParser, RangeQuery, RegexQuery, TermQuery};
 use tantivy::schema::*;
 use tantivy::Term;
 use tantivy::TantivyError;
-use tantivy::{doc, Index, IndexWriter};
+use tantivy::snippet::SnippetGenerator;
+use tantivy::{doc, DocAddress, Index, IndexReader, Order, ReloadPolicy, Searcher};
 use tokio;
 // regex is referenced directly as `regex::...`
 
-pub async fn create_index(path: &str) -> Result<(), String> {
-    //El index se va a guardar en ~/.cache/aleph/index
-    //Si no existe el path se crea
-    let home = dirs::home_dir().unwrap();
-    let idx_path = home.join(".cache/aleph/index").join(path);
-    let idx_path = idx_path.as_path();
-    if !idx_path.exists() {
-        fs::create_dir_all(idx_path).map_err(|e| e.to_string())?;
-    }
-
-    let mut schema_builder = Schema::builder();
-
-    schema_builder.add_text_field("path", STORED | STRING);
-    schema_builder.add_text_field("filename", TEXT | STORED);
-    schema_builder.add_text_field("extension", STRING | STORED);
-
-    let schema = schema_builder.build();
-
-    //  Abrir o crear el índice de forma segura
-    let index: Index = match Index::create_in_dir(idx_path, schema.clone()) {
-        Ok(idx) => idx, // creado de cero
-        Err(TantivyError::IndexAlreadyExists) => {
-            // fs::remove_file(idx_path.join("meta.json")).map_err(|e| e.to_string())?;
-            // Index::create_in_dir(idx_path, schema.clone()).map_err(|e| e.to_string())?
-            //si lo encuentra lo abre
-            Index::open_in_dir(idx_path).map_err(|e| e.to_string())?
-        } // ya existía
-        Err(e) => return Err(e.to_string()), // otro error
+// Por relevancia se re-rankean siempre los mismos candidatos, pida la página que se
+// pida: si dependieran del offset, al pasar de página el orden cambiaría y habría
+// resultados repetidos o salteados. Más allá de estos no hay más páginas
+const CANDIDATE_POOL: usize = 300;
+
+// Un solo índice para todas las raíces: cada documento guarda de qué raíz viene
+fn index_dir() -> Result<PathBuf, AlephError> {
+    let home = dirs::home_dir().ok_or(AlephError::NoHomeDir)?;
+    Ok(home.join(".cache/aleph/files"))
+}
+
+#[derive(Clone, Copy)]
+struct FileFields {
+    path: Field,
+    filename: Field,
+    // Falta en índices de versiones anteriores (mientras se migran se sigue buscando en ellos)
+    filename_ngram: Option<Field>,
+    name_sort: Option<Field>,
+    extension: Field,
+    root: Field,
+    ancestors: Field,
+    kind: Field,
+    content: Field,
+    title: Field,
+    author: Field,
+    mtime: Field,
+    size: Field,
+}
+
+impl FileFields {
+    fn from_schema(schema: &Schema) -> Result<Self, String> {
+        let field = |name: &str| {
+            schema
+                .get_field(name)
+                .map_err(|_| format!("field {} not found", name))
+        };
+        Ok(FileFields {
+            path: field("path")?,
+            filename: field("filename")?,
+            filename_ngram: schema.get_field("filename_ngram").ok(),
+            name_sort: schema.get_field("name_sort").ok(),
+            extension: field("extension")?,
+            root: field("root")?,
+            ancestors: field("ancestors")?,
+            kind: field("kind")?,
+            content: field("content")?,
+            title: field("title")?,
+            author: field("author")?,
+            mtime: field("mtime")?,
+            size: field("size")?,
+        })
+    }
+}
+
+struct FileIndex {
+    index: Index,
+    reader: IndexReader,
+    writer: WriterHandle,
+    fields: FileFields,
+    // Carpeta del índice (cambia mientras se usa uno recién migrado)
+    dir: PathBuf,
+}
+
+impl FileIndex {
+    fn open(index: Index, dir: PathBuf) -> Result<Self, String> {
+        let reader = index
+            .reader_builder()
+            .reload_policy(ReloadPolicy::OnCommitWithDelay)
+            .try_into()
+            .map_err(|e: TantivyError| e.to_string())?;
+        let fields = FileFields::from_schema(&index.schema())?;
+        let writer = WriterHandle::spawn(&index, "files")?;
+        Ok(FileIndex {
+            index,
+            reader,
+            writer,
+            fields,
+            dir,
+        })
+    }
+}
+
+fn current_index() -> &'static RwLock<Option<Arc<FileIndex>>> {
+    static CURRENT: OnceLock<RwLock<Option<Arc<FileIndex>>>> = OnceLock::new();
+    CURRENT.get_or_init(|| RwLock::new(None))
+}
+
+// El índice en uso; se abre una sola vez y el reader se recarga solo en cada commit.
+// Si el de disco es de otra versión del schema se sigue usando mientras se arma el
+// nuevo en background (ver migrate_index). Si no se puede abrir, el índice no está
+// listo para buscar
+fn file_index() -> Result<Arc<FileIndex>, AlephError> {
+    if let Some(file_index) = current_index().read().unwrap().as_ref() {
+        return Ok(file_index.clone());
+    }
+    let mut current = current_index().write().unwrap();
+    if let Some(file_index) = current.as_ref() {
+        return Ok(file_index.clone());
+    }
+
+    let dir = index_dir()?;
+    let readable = |s: &Schema| FileFields::from_schema(s).is_ok();
+    let opened = schema::open(&schema::FILES, &dir, readable)?;
+    let file_index = match opened {
+        Opened::Created(index) | Opened::Current(index) => {
+            FileIndex::open(index, dir)?
+        }
+        Opened::Migrate {
+            old,
+            fresh,
+            fresh_dir,
+        } => {
+            let old = FileIndex::open(old, dir)?;
+            let fresh = FileIndex::open(fresh, fresh_dir)?;
+            tokio::spawn(migrate_index(fresh));
+            old
+        }
     };
+    let file_index = Arc::new(file_index);
+    *current = Some(file_index.clone());
+    Ok(file_index)
+}
 
-    let mut index_writer: IndexWriter = index
-        .writer_with_num_threads(10, 200_000_000)
-        .map_err(|e| e.to_string())?;
-    //B: let writer = Arc::new(index.writer(50_000_000)?);
+// Arma el índice nuevo con todas las raíces y lo pone en uso cuando termina.
+// Lo que cambió mientras tanto fue al índice viejo: se recupera reconciliando
+async fn migrate_index(fresh: FileIndex) {
+    let _guard = bulk_lock().lock().await;
+    let fresh = Arc::new(fresh);
+    let roots = roots::roots();
+    for root in &roots {
+        let job = status::start_job(JobKind::Migrate, root_key(root));
+        // Lo que tenía la raíz en el índice viejo da una idea de cuánto falta
+        if let Ok(old) = file_index() {
+            job.expect(root_doc_count(&old, &root_key(root)) as u64);
+        }
+        let result = create_index(&fresh, root, &job).await;
+        job.finish(&result);
+        if let Err(e) = result {
+            println!("Error migrating index for {:?}: {}", root.path, e);
+        }
+    }
+    if let Err(e) = schema::finish(&schema::FILES, &fresh.dir) {
+        println!("Error finishing index migration: {}", e);
+        return;
+    }
+    *current_index().write().unwrap() = Some(fresh);
+
+    for root in &roots {
+        let job = status::start_job(JobKind::Reconcile, root_key(root));
+        let result = reconcile_root(root, &job).await;
+        job.finish(&result);
+        if let Err(e) = result {
+            println!("Error indexing {:?}: {}", root.path, e);
+        }
+    }
+}
+
+fn root_key(root: &IndexRoot) -> String {
+    root.path.display().to_string()
+}
+
+// Raíces que ya se indexaron completas al menos una vez (se guarda al lado del índice)
+fn indexed_roots_path(file_index: &FileIndex) -> PathBuf {
+    file_index.dir.join("indexed_roots.json")
+}
+
+fn indexed_roots(file_index: &FileIndex) -> HashSet<String> {
+    fs::read_to_string(indexed_roots_path(file_index))
+        .ok()
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default()
+}
+
+fn set_root_indexed(file_index: &FileIndex, root: &IndexRoot, indexed: bool) -> Result<(), String> {
+    static MANIFEST_LOCK: Mutex<()> = Mutex::new(());
+    let _guard = MANIFEST_LOCK.lock().unwrap();
+
+    let mut set = indexed_roots(file_index);
+    if indexed {
+        set.insert(root_key(root));
+    } else {
+        set.remove(&root_key(root));
+    }
+    let raw = serde_json::to_string(&set).map_err(|e| e.to_string())?;
+    fs::write(indexed_roots_path(file_index), raw).map_err(|e| e.to_string())
+}
+
+// Los índices masivos se arman de a una raíz por vez
+fn bulk_lock() -> &'static tokio::sync::Mutex<()> {
+    static BULK_LOCK: OnceLock<tokio::sync::Mutex<()>> = OnceLock::new();
+    BULK_LOCK.get_or_init(|| tokio::sync::Mutex::new(()))
+}
 
-    let path_f = schema.get_field("path").unwrap();
-    let filename = schema.get_field("filename").unwrap();
-    let ext_f = schema.get_field("extension").unwrap();
+// Lo que se indexa: archivos y carpetas
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EntryKind {
+    File,
+    Dir,
+}
+
+impl EntryKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            EntryKind::File => "file",
+            EntryKind::Dir => "dir",
+        }
+    }
+
+    // El valor guardado en el field kind (los documentos viejos sin él son archivos)
+    fn from_stored(value: Option<&str>) -> Self {
+        match value {
+            Some("dir") => EntryKind::Dir,
+            _ => EntryKind::File,
+        }
+    }
+}
+
+// Documento de un archivo o carpeta de la raíz `root_str`
+fn build_doc(fields: &FileFields, path: &Path, root_str: &str, kind: EntryKind) -> TantivyDocument {
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy())
+        .unwrap_or_default();
+    // Las carpetas no tienen extensión (aunque tengan un punto en el nombre)
+    let ext = match kind {
+        EntryKind::File => path
+            .extension()
+            .and_then(|s| s.to_str())
+            .unwrap_or("")
+            .to_lowercase(),
+        EntryKind::Dir => String::new(),
+    };
 
-    //Vamos a indexar todo
-    let root_dir = home.join(path);
-    let root_dir = root_dir.as_path();
+    let mut doc = doc!(
+        fields.path => path.display().to_string(),
+        fields.filename => name.as_ref(),
+        fields.extension => ext.as_str(),
+        fields.root => root_str,
+        fields.kind => kind.as_str(),
+    );
+    if let Some(filename_ngram) = fields.filename_ngram {
+        doc.add_text(filename_ngram, name.as_ref());
+    }
+    if let Some(name_sort) = fields.name_sort {
+        doc.add_text(name_sort, tokenizer::fold(&name));
+    }
+    for ancestor in path
+        .ancestors()
+        .skip(1)
+        .take_while(|a| a.starts_with(root_str))
+    {
+        doc.add_text(fields.ancestors, ancestor.display().to_string());
+    }
+    if let Ok(meta) = fs::metadata(path) {
+        doc.add_i64(fields.mtime, meta.mtime());
+        // El tamaño de una carpeta no dice nada útil: size: filtra solo archivos
+        if kind == EntryKind::File {
+            doc.add_u64(fields.size, meta.len());
+        }
+    }
+    if kind == EntryKind::File {
+        add_content(&mut doc, fields, path, &ext);
+    }
+    doc
+}
 
-    WalkDir::new(root_dir)
+// Texto y metadatos del interior del archivo, si hay un extractor para su tipo
+fn add_content(doc: &mut TantivyDocument, fields: &FileFields, path: &Path, ext: &str) {
+    let extracted = match content::extract(path, ext) {
+        Some(extracted) => extracted,
+        None => return,
+    };
+    // En NFC, que es sobre lo que el tokenizer calcula los offsets de los snippets
+    doc.add_text(fields.content, tokenizer::nfc(&extracted.text));
+    if let Some(title) = &extracted.title {
+        doc.add_text(fields.title, title);
+    }
+    if let Some(author) = &extracted.author {
+        doc.add_text(fields.author, author);
+    }
+}
+
+// Recorre `start` (la raíz o una carpeta adentro) con las reglas de la raíz.
+// Las raíces anidadas se indexan aparte, no se recorren acá
+fn walk(filter: &RootFilter, start: &Path) -> WalkDirGeneric<(IgnoreStack, ())> {
+    let root_path = filter.root.path.clone();
+    let nested: Vec<PathBuf> = roots::roots()
+        .into_iter()
+        .map(|r| r.path)
+        .filter(|p| p != &root_path && p.starts_with(&root_path))
+        .collect();
+    let walk_filter = filter.clone();
+
+    // El estado de cada directorio es la pila de .gitignore/.ignore desde la raíz
+    let mut walker = WalkDirGeneric::<(IgnoreStack, ())>::new(start)
         .skip_hidden(true)
-        .follow_links(true)
+        .follow_links(filter.root.follow_symlinks)
         .parallelism(Parallelism::RayonNewPool(8))
-        .into_iter()
-        .par_bridge()
-        .for_each(|res| {
-            if let Ok(entry) = res {
-                //filtro si no es un directorio
-                if entry.file_type().is_file() {
-                    let path = entry.path().display().to_string();
-                    let name = entry.file_name().to_string_lossy();
-                    let ext = entry
-                        .path()
-                        .extension()
-                        .and_then(|s| s.to_str())
-                        .unwrap_or("")
-                        .to_lowercase();
-                    let doc = doc!(
-                        path_f => path,
-                        filename => name.as_ref(),
-                        ext_f => ext.as_str(),
-                    );
-                    index_writer.add_document(doc).unwrap();
-                }
+        .root_read_dir_state(IgnoreStack::above(&root_path, start))
+        .process_read_dir(move |depth, dir, ignores, children| {
+            // depth == None es la lectura del padre de `start`: `start` siempre entra
+            if depth.is_none() {
+                return;
             }
+            ignores.push_dir(dir);
+            // Podar carpetas excluidas o ignoradas para no bajar a ellas
+            children.retain(|res| match res {
+                Ok(entry) => {
+                    let path = entry.path();
+                    !walk_filter.is_excluded(&path)
+                        && !nested.contains(&path)
+                        && !ignores.is_ignored(&path, entry.file_type().is_dir())
+                }
+                Err(_) => true,
+            });
         });
-    index_writer.commit().map_err(|e| e.to_string())?;
-    index_writer
-        .wait_merging_threads()
+    if let Some(max_depth) = filter.root.max_depth {
+        let start_depth = start
+            .strip_prefix(&root_path)
+            .map(|rel| rel.components().count())
+            .unwrap_or(0);
+        walker = walker.max_depth(max_depth.saturating_sub(start_depth));
+    }
+    walker
+}
+
+// Archivos que acepten las reglas y carpetas no excluidas (la raíz incluida)
+fn entry_kind(filter: &RootFilter, path: &Path, file_type: fs::FileType) -> Option<EntryKind> {
+    if file_type.is_dir() {
+        Some(EntryKind::Dir)
+    } else if file_type.is_file() && filter.accepts_file(path) {
+        Some(EntryKind::File)
+    } else {
+        None
+    }
+}
+
+async fn create_index(file_index: &FileIndex, root: &IndexRoot, job: &JobGuard) -> Result<(), String> {
+    let filter = root.filter()?;
+    let fields = file_index.fields;
+
+    let writer = &file_index.writer;
+    let root_str = root_key(root);
+
+    // Si ya estaba indexada, lo que tenía sirve para estimar cuánto falta
+    let previous = root_doc_count(file_index, &root_str);
+    if previous > 0 {
+        job.expect(previous as u64);
+    }
+
+    // Reindexar la raíz desde cero: lo que hubiera quedado de ella se borra primero
+    writer.clear(Term::from_field_text(fields.root, &root_str))?;
+
+    // El recorrido y la lectura de contenidos bloquean: van al pool de tareas bloqueantes.
+    // La primera vez no hay con qué estimar cuánto falta, así que no hay ETA
+    let progress = job.progress();
+    let walk_writer = writer.clone();
+    let walk_root = root.path.clone();
+    let walk_root_str = root_str.clone();
+    tokio::task::spawn_blocking(move || {
+        walk(&filter, &walk_root)
+            .into_iter()
+            .par_bridge()
+            .for_each(|res| {
+                if let Ok(entry) = res {
+                    let path = entry.path();
+                    progress.scanned(&path, entry.file_type().is_dir());
+                    if let Some(kind) = entry_kind(&filter, &path, entry.file_type()) {
+                        let doc = build_doc(&fields, &path, &walk_root_str, kind);
+                        if walk_writer.add(doc).is_ok() {
+                            progress.indexed(1);
+                        }
+                    }
+                }
+            });
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+    writer.commit().await?;
+
+    set_root_indexed(file_index, root, true)?;
+    static LEGACY_REMOVED: OnceLock<()> = OnceLock::new();
+    LEGACY_REMOVED.get_or_init(remove_legacy_index);
+    Ok(())
+}
+
+// Los índices viejos (uno por carpeta en ~/.cache/aleph/index) ya no se usan: se borran
+// una vez, cuando el índice nuevo ya tiene algo armado
+fn remove_legacy_index() {
+    let Some(legacy_dir) = dirs::home_dir().map(|home| home.join(".cache/aleph/index")) else {
+        return;
+    };
+    if legacy_dir.exists() {
+        if let Err(e) = fs::remove_dir_all(&legacy_dir) {
+            println!("Error removing old index {:?}: {}", legacy_dir, e);
+        }
+    }
+}
+
+// Lo que se compara contra el disco para saber si un documento quedó viejo
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Stamp {
+    kind: EntryKind,
+    mtime: Option<i64>,
+    size: Option<u64>,
+}
+
+impl Stamp {
+    // Lo mismo que guarda build_doc
+    fn of(meta: &fs::Metadata, kind: EntryKind) -> Self {
+        Stamp {
+            kind,
+            mtime: Some(meta.mtime()),
+            size: (kind == EntryKind::File).then_some(meta.len()),
+        }
+    }
+}
+
+// Diferencias entre el índice y el disco para una raíz
+#[derive(Debug, Default, PartialEq)]
+struct Reconciliation {
+    // Nuevos o modificados: hay que volver a armar su documento
+    stale: Vec<(PathBuf, EntryKind)>,
+    // Ya no están en el disco (o las reglas de la raíz los dejan afuera)
+    missing: Vec<String>,
+}
+
+fn reconcile(mut indexed: HashMap<String, Stamp>, on_disk: Vec<(PathBuf, Stamp)>) -> Reconciliation {
+    let mut result = Reconciliation::default();
+    for (path, stamp) in on_disk {
+        match indexed.remove(&path.display().to_string()) {
+            Some(stored) if stored == stamp => {}
+            _ => result.stale.push((path, stamp.kind)),
+        }
+    }
+    result.missing = indexed.into_keys().collect();
+    result
+}
+
+// Path -> Stamp de todos los documentos de la raíz
+fn indexed_stamps(file_index: &FileIndex, root_str: &str) -> Result<HashMap<String, Stamp>, String> {
+    let fields = file_index.fields;
+    let searcher = file_index.reader.searcher();
+    let query = TermQuery::new(
+        Term::from_field_text(fields.root, root_str),
+        IndexRecordOption::Basic,
+    );
+    let addresses = searcher
+        .search(&query, &DocSetCollector)
         .map_err(|e| e.to_string())?;
+
+    let mut stamps = HashMap::with_capacity(addresses.len());
+    for address in addresses {
+        let doc: TantivyDocument = searcher.doc(address).map_err(|e| e.to_string())?;
+        let Some(path) = doc.get_first(fields.path).and_then(|v| v.as_str()) else {
+            continue;
+        };
+        let stamp = Stamp {
+            kind: EntryKind::from_stored(doc.get_first(fields.kind).and_then(|v| v.as_str())),
+            mtime: doc.get_first(fields.mtime).and_then(|v| v.as_i64()),
+            size: doc.get_first(fields.size).and_then(|v| v.as_u64()),
+        };
+        // Un path repetido (versiones viejas agregaban sin reemplazar) se reescribe
+        // para que quede un solo documento
+        if stamps.insert(path.to_string(), stamp).is_some() {
+            stamps.insert(path.to_string(), Stamp { mtime: None, ..stamp });
+        }
+    }
+    Ok(stamps)
+}
+
+// Cuántos documentos se arman por tanda al reconciliar, para no tener todo en memoria
+const RECONCILE_CHUNK: usize = 500;
+
+// Pone al día una raíz ya indexada con lo que cambió mientras Aleph no corría:
+// solo se rearman los documentos nuevos o modificados y se borran los que faltan
+async fn reconcile_root(root: &IndexRoot, job: &JobGuard) -> Result<(), String> {
+    let filter = root.filter()?;
+    let file_index = file_index()?;
+    let fields = file_index.fields;
+    let writer = &file_index.writer;
+    let root_str = root_key(root);
+
+    // Lo que el watcher ya haya mandado tiene que estar a la vista del searcher
+    writer.commit().await?;
+    file_index.reader.reload().map_err(|e| e.to_string())?;
+    let indexed = indexed_stamps(&file_index, &root_str)?;
+
+    let on_disk: Vec<(PathBuf, Stamp)> = walk(&filter, &root.path)
+        .into_iter()
+        .par_bridge()
+        .filter_map(|res| {
+            let entry = res.ok()?;
+            let path = entry.path();
+            job.scanned(&path, entry.file_type().is_dir());
+            let kind = entry_kind(&filter, &path, entry.file_type())?;
+            let meta = fs::metadata(&path).ok()?;
+            Some((path, Stamp::of(&meta, kind)))
+        })
+        .collect();
+
+    let diff = reconcile(indexed, on_disk);
+    job.expect(diff.stale.len() as u64);
+    for chunk in diff.stale.chunks(RECONCILE_CHUNK) {
+        let changes: Vec<Change> = chunk
+            .par_iter()
+            .map(|(path, kind)| {
+                let key = Term::from_field_text(fields.path, &path.display().to_string());
+                Change::Upsert(key, build_doc(&fields, path, &root_str, *kind))
+            })
+            .collect();
+        writer.apply(changes)?;
+        job.indexed(chunk.len() as u64);
+    }
+    writer.apply(
+        diff.missing
+            .iter()
+            .map(|p| Change::Delete(Term::from_field_text(fields.path, p)))
+            .collect(),
+    )?;
+    writer.commit().await?;
+
+    println!(
+        "Reconciled {}: {} updated, {} removed",
+        root_str,
+        diff.stale.len(),
+        diff.missing.len()
+    );
     Ok(())
 }
 
+const SNIPPET_CHARS: usize = 120;
+
+// `limit`/`offset` piden una página de resultados y `sort` su orden (por defecto,
+// relevancia). Una página que vuelve con menos de `limit` resultados es la última
 #[tauri::command]
 pub async fn search_index(
     query: &str,
-) -> Result<Vec<(String, String, f32, Option<String>)>, String> {
-    //Deberia chequear si se creo el index
-    let home = dirs::home_dir().unwrap();
-    let idx_path = home.join(".cache/aleph/index");
-    let idx_path = idx_path.as_path();
-
-    let folders = [
-        "Documents",
-        "Downloads",
-        "Pictures",
-        "Music",
-        "Movies",
-        "Library",
-        "Public",
-    ];
-
-    let search_in_index = |index: &Index,
-                           limit: usize|
-     -> Result<Vec<(String, String, f32, Option<String>)>, String> {
-        let reader = index.reader().map_err(|e| e.to_string())?;
-        // Ensure reader reflects the most recent commits (deletes/adds)
-        reader.reload().map_err(|e| e.to_string())?;
-
-        //let min_score = 0.1;
-        let schema = index.schema();
-
-        let path_f = schema.get_field("path").unwrap();
-        let filename = schema.get_field("filename").unwrap();
-        let ext_f = schema.get_field("extension").unwrap();
-
-        let searcher = reader.searcher();
-
-        let mut query_parser = QueryParser::for_index(&index, vec![path_f, filename, ext_f]);
-        query_parser.set_field_fuzzy(filename, false, 1, true);
-
-        let query_str = &query;
-        let fuzzy_query = query_parser
-            .parse_query(query)
-            .map_err(|e| e.to_string())?;
-
-        // Substring case-insensitive using RegexQuery on path
-        let escaped = regex::escape(query);
-        let ci_regex = format!("(?i).*{}.*", escaped);
-        let substring_query = RegexQuery::from_pattern(&ci_regex, path_f)
-            .map_err(|e| e.to_string())?;
-
-        // Combine fuzzy filename match OR substring path match
-        let combined = BooleanQuery::new(vec![
-            (Occur::Should, Box::new(fuzzy_query)),
-            (Occur::Should, Box::new(substring_query)),
-        ]);
+    root: Option<String>,
+    limit: Option<usize>,
+    offset: Option<usize>,
+    sort: Option<SortMode>,
+) -> Result<Vec<SearchResult>, AlephError> {
+    search_files(query, root, Page::new(limit, offset, sort)?, false)
+}
+
+// Igual que search_index, con el desglose del puntaje de cada resultado
+#[tauri::command]
+pub async fn explain_search(
+    query: &str,
+    root: Option<String>,
+    limit: Option<usize>,
+    offset: Option<usize>,
+    sort: Option<SortMode>,
+) -> Result<Vec<SearchResult>, AlephError> {
+    search_files(query, root, Page::new(limit, offset, sort)?, true)
+}
+
+// Los `limit` documentos desde `offset` en el orden pedido, con el puntaje de tantivy
+// (0 si el orden no es por relevancia). Por relevancia (o por nombre en un índice
+// viejo) el orden final se decide después, en memoria
+fn ranked_docs(
+    file_index: &FileIndex,
+    searcher: &Searcher,
+    query: &dyn Query,
+    sort: SortMode,
+    limit: usize,
+    offset: usize,
+) -> Result<Vec<(f32, DocAddress)>, String> {
+    let fields = file_index.fields;
+    let schema = file_index.index.schema();
+    let field_name = |field: Field| schema.get_field_name(field).to_string();
+    let top = TopDocs::with_limit(limit).and_offset(offset);
+    let unscored = |docs: Vec<DocAddress>| -> Vec<(f32, DocAddress)> {
+        docs.into_iter().map(|d| (0.0, d)).collect()
+    };
+
+    let docs = match (sort, fields.name_sort) {
+        (SortMode::Modified, _) => unscored(
+            searcher
+                .search(query, &top.order_by_fast_field::<i64>(field_name(fields.mtime), Order::Desc))
+                .map_err(|e| e.to_string())?
+                .into_iter()
+                .map(|(_, d)| d)
+                .collect(),
+        ),
+        (SortMode::Size, _) => unscored(
+            searcher
+                .search(query, &top.order_by_fast_field::<u64>(field_name(fields.size), Order::Desc))
+                .map_err(|e| e.to_string())?
+                .into_iter()
+                .map(|(_, d)| d)
+                .collect(),
+        ),
+        (SortMode::Name, Some(name_sort)) => unscored(
+            searcher
+                .search(query, &top.order_by_string_fast_field(field_name(name_sort), Order::Asc))
+                .map_err(|e| e.to_string())?
+                .into_iter()
+                .map(|(_, d)| d)
+                .collect(),
+        ),
+        // Un índice viejo sin el nombre para ordenar se ordena después, en memoria
+        (SortMode::Relevance, _) | (SortMode::Name, None) => {
+            searcher.search(query, &top).map_err(|e| e.to_string())?
+        }
+    };
+    Ok(docs)
+}
 
-        let top_docs = searcher
-            .search(&combined, &TopDocs::with_limit(limit))
-            .map_err(|e| e.to_string())?;
+pub fn search_files(
+    query: &str,
+    root: Option<String>,
+    page: Page,
+    explain: bool,
+) -> Result<Vec<SearchResult>, AlephError> {
+    let profile = &settings::settings().ranking;
+    // Iniciar watchers y armar en background los índices que falten
+    for index_root in roots::roots() {
+        spawn_root(index_root);
+    }
 
-        let mut first_vec: Vec<(String, String, f32, Option<String>)> =
-            Vec::with_capacity(top_docs.len());
+    let file_index = file_index()?;
+    if first_build_running(&file_index, root.as_deref()) {
+        return Err(AlephError::IndexNotReady("se está indexando por primera vez".to_string()));
+    }
+    let fields = file_index.fields;
+    let searcher = file_index.reader.searcher();
+
+    // La query se interpreta con el lenguaje de Aleph (ext:, in:, kind:, modified:, size:);
+    // lo que no sea un operador válido queda como texto, así que nunca falla por sintaxis
+    let parsed = query::parse(query);
+    let text = parsed.text();
+    if text.trim().is_empty() && !parsed.has_filters() {
+        return Ok(Vec::new());
+    }
 
+    let mut clauses: Vec<(Occur, Box<dyn Query>)> = Vec::new();
+    if let Some(text_query) = text_query(&file_index, &parsed) {
+        clauses.push((Occur::Must, text_query));
+    }
+    for filter in filter_queries(&fields, &parsed) {
+        clauses.push((Occur::Must, filter));
+    }
+
+    // Lo que ya se eligió para esta query sube entre los candidatos. Es Should junto a
+    // cláusulas Must (el texto, o los filtros si no hay texto), así que solo cuenta
+    // para documentos que ya matchean
+    let picked = if clauses.is_empty() { Vec::new() } else { clicks::picked(query) };
+    for path in picked {
+        let term = TermQuery::new(Term::from_field_text(fields.path, &path), IndexRecordOption::Basic);
+        clauses.push((Occur::Should, Box::new(BoostQuery::new(Box::new(term), profile.picked_boost))));
+    }
+
+    // Opcionalmente restringido a una sola raíz
+    if let Some(root) = root {
+        clauses.push((
+            Occur::Must,
+            Box::new(TermQuery::new(
+                Term::from_field_text(fields.root, &root),
+                IndexRecordOption::Basic,
+            )),
+        ));
+    }
+    let final_query = BooleanQuery::new(clauses);
+
+    // Snippets: los términos de la query tal cual (sin fuzzy) sobre el contenido
+    let snippet_generator = if text.is_empty() {
+        None
+    } else {
+        let (content_query, _) = QueryParser::for_index(&file_index.index, vec![fields.content])
+            .parse_query_lenient(&parsed.parser_input());
+        SnippetGenerator::create(&searcher, &content_query, fields.content)
+            .ok()
+            .map(|mut generator| {
+                generator.set_max_num_chars(SNIPPET_CHARS);
+                generator
+            })
+    };
+
+    // Por relevancia se re-rankean siempre los mismos candidatos. En los otros órdenes
+    // tantivy ya ordena, y se trae desde el principio hasta el final de la página: lo
+    // que ya no existe se descarta antes de paginar, y lo que falte se completa con los
+    // que siguen, así las páginas no vuelven cortas
+    let in_memory = matches!(
+        (page.sort, fields.name_sort),
+        (SortMode::Relevance, _) | (SortMode::Name, None)
+    );
+    let wanted = if in_memory { CANDIDATE_POOL } else { page.offset + page.limit };
+
+    // Primero se puntúan todos los candidatos; snippets e íconos (lo caro) solo para
+    // los de la página que se devuelve
+    let mut results: Vec<(SearchResult, TantivyDocument)> = Vec::with_capacity(wanted);
+    let mut fetched = 0;
+    while results.len() < wanted {
+        let missing = wanted - results.len();
+        let top_docs = ranked_docs(&file_index, &searcher, &final_query, page.sort, missing, fetched)?;
+        let exhausted = top_docs.len() < missing;
+        fetched += top_docs.len();
         for (score, doc_address) in top_docs {
             let retrieved_doc: TantivyDocument =
                 searcher.doc(doc_address).map_err(|e| e.to_string())?;
             let name = retrieved_doc
-                .get_first(filename)
+                .get_first(fields.filename)
                 .and_then(|v| v.as_str())
                 .unwrap_or_default()
                 .to_owned();
 
             let path = retrieved_doc
-                .get_first(path_f)
+                .get_first(fields.path)
                 .and_then(|v| v.as_str())
                 .unwrap_or_default()
                 .to_owned();
@@ -177,180 +757,250 @@ pub async fn search_index(
                 continue;
             }
 
-            let extension = retrieved_doc
-                .get_first(ext_f)
-                .and_then(|v| v.as_str())
-                .unwrap_or_default();
+            let kind = EntryKind::from_stored(retrieved_doc.get_first(fields.kind).and_then(|v| v.as_str()));
+            let name_match = fuzzy::fuzzy_match(&text, &name);
+            let path_match = fuzzy::fuzzy_match(&text, &path);
+            let mut explanation = calculate_contextual_score(
+                &name,
+                &path,
+                score,
+                &text,
+                kind,
+                name_match.as_ref(),
+                path_match.as_ref(),
+            );
+            explanation.apply("clicks", clicks::boost(query, &path));
+
+            let kind = match kind {
+                EntryKind::File => ResultKind::File,
+                EntryKind::Dir => ResultKind::Dir,
+            };
+            let score = explanation.score;
+            let result = SearchResult {
+                title_ranges: name_match.map(|m| m.ranges).unwrap_or_default(),
+                subtitle_ranges: path_match.map(|m| m.ranges).unwrap_or_default(),
+                explain: explain.then_some(explanation),
+                ..SearchResult::new(kind, name, path, score)
+            };
+            results.push((result, retrieved_doc));
+        }
+        if in_memory || exhausted {
+            break;
+        }
+    }
+
+    // Los otros órdenes ya vienen ordenados desde tantivy
+    match (page.sort, fields.name_sort) {
+        (SortMode::Relevance, _) => results.sort_by(|a, b| b.0.score.total_cmp(&a.0.score)),
+        (SortMode::Name, None) => results.sort_by_cached_key(|(hit, _)| tokenizer::fold(&hit.title)),
+        _ => {}
+    }
+    let results = page.slice(results);
 
-            let better_score = calculate_contextual_score(&name, &path, score, &query_str);
+    let q_folded = tokenizer::fold(&text);
+    let page_results = results
+        .into_iter()
+        .map(|(mut result, retrieved_doc)| {
+            // Si la query aparece en el nombre/path es un match por nombre;
+            // si no, y el contenido tiene los términos resaltados, vino del contenido
+            let snippet = snippet_generator
+                .as_ref()
+                .map(|g| g.snippet_from_doc(&retrieved_doc))
+                .filter(|s| !s.is_empty());
+            result.matched = if tokenizer::fold(&result.path).contains(&q_folded) || snippet.is_none() {
+                MatchSource::Name
+            } else {
+                MatchSource::Content
+            };
+            result.snippet = match result.matched {
+                MatchSource::Content => snippet.map(|s| s.fragment().trim().to_owned()),
+                MatchSource::Name => None,
+            };
 
             // Get icon for the file
-            let icon = if icons::is_executable(&path) {
+            let extension = retrieved_doc
+                .get_first(fields.extension)
+                .and_then(|v| v.as_str())
+                .unwrap_or_default();
+            result.icon = if icons::is_executable(&result.path) {
                 // If it's an app, extract app icon
-                icons::extract_app_icon(&path)
+                icons::extract_app_icon(&result.path)
+            } else if result.kind == ResultKind::Dir {
+                // Las carpetas usan el ícono de carpeta del frontend
+                None
             } else {
                 // Otherwise get file type icon
-                icons::get_file_icon(&path, extension)
+                icons::get_file_icon(&result.path, extension)
             };
+            result
+        })
+        .collect();
+    Ok(page_results)
+}
 
-            first_vec.push((name, path, better_score, icon));
-        }
-        let top_docs_vec: Vec<(String, String, f32, Option<String>)> = first_vec
-            .into_iter()
-            //.filter(|(_, _, score, _)| *score > min_score)
-            .collect();
-
-        Ok(top_docs_vec)
-    };
+// Texto libre: términos (fuzzy en el nombre) OR prefijos/abreviaturas de las partes del nombre
+fn text_query(file_index: &FileIndex, parsed: &ParsedQuery) -> Option<Box<dyn Query>> {
+    let text = parsed.text();
+    if text.trim().is_empty() {
+        return None;
+    }
+    let fields = file_index.fields;
+
+    let mut query_parser = QueryParser::for_index(
+        &file_index.index,
+        vec![
+            fields.path,
+            fields.filename,
+            fields.extension,
+            fields.content,
+            fields.title,
+            fields.author,
+        ],
+    );
+    if !tokenizer::has_cjk(&text) {
+        query_parser.set_field_fuzzy(fields.filename, false, 1, true);
+    }
+    query_parser.set_field_boost(fields.content, settings::settings().ranking.content_boost);
+    let (fuzzy_query, _) = query_parser.parse_query_lenient(&parsed.parser_input());
+
+    let mut should: Vec<(Occur, Box<dyn Query>)> = vec![(Occur::Should, fuzzy_query)];
+    if let Some(prefix_query) = fields
+        .filename_ngram
+        .and_then(|field| tokenizer::abbreviation_query(field, &text))
+    {
+        should.push((Occur::Should, prefix_query));
+    }
+    Some(Box::new(BooleanQuery::new(should)))
+}
 
-    if !idx_path.exists() {
-        fs::create_dir_all(idx_path).map_err(|e| e.to_string())?;
-    }
-
-    let desktop_path = idx_path.join("Desktop");
-    //  Abrir o crear el índice de forma segura
-    let index = match Index::open_in_dir(&desktop_path) {
-        Ok(idx) => idx, // lo encuentra, lo habre
-        Err(_) => {
-            // Índice no existe o hay otro error
-            // Crear el índice y luego abrirlo
-            create_index("Desktop").await.map_err(|e| e.to_string())?;
-            let idx_path = home.join(".cache/aleph/index/Desktop");
-            let idx_path = idx_path.as_path();
-            Index::open_in_dir(idx_path).map_err(|e| e.to_string())?
-        }
-    };
+// Alguno de los términos de un field de texto crudo
+fn any_term(field: Field, values: &[&str]) -> Box<dyn Query> {
+    let should = values
+        .iter()
+        .map(|v| {
+            let term = TermQuery::new(Term::from_field_text(field, v), IndexRecordOption::Basic);
+            (Occur::Should, Box::new(term) as Box<dyn Query>)
+        })
+        .collect();
+    Box::new(BooleanQuery::new(should))
+}
 
-    let destkop_dir = dirs::desktop_dir().unwrap();
+// in:Downloads matchea cualquier carpeta con ese nombre; in:~/src o in:/tmp, un path
+fn dir_query(fields: &FileFields, dir: &str) -> Option<Box<dyn Query>> {
+    if dir.starts_with('/') || dir.starts_with('~') {
+        let path = roots::expand_home(dir).ok()?;
+        let path = path.to_string_lossy();
+        let path = match path.trim_end_matches('/') {
+            "" => "/",
+            trimmed => trimmed,
+        };
+        return Some(Box::new(TermQuery::new(
+            Term::from_field_text(fields.ancestors, path),
+            IndexRecordOption::Basic,
+        )));
+    }
+    let pattern = format!("(?i).*/{}/.*", regex::escape(dir.trim_matches('/')));
+    let query = RegexQuery::from_pattern(&pattern, fields.path).ok()?;
+    Some(Box::new(query))
+}
 
-    // Iniciar watchers una sola vez por ejecución
-    static STARTED_WATCHERS: AtomicBool = AtomicBool::new(false);
-    if !STARTED_WATCHERS.swap(true, Ordering::SeqCst) {
-        // Desktop
-        let desktop_watch = destkop_dir.clone();
-        let _ = tokio::spawn(async move { let _ = async_watch(desktop_watch).await; });
+fn filter_queries(fields: &FileFields, parsed: &ParsedQuery) -> Vec<Box<dyn Query>> {
+    let mut filters: Vec<Box<dyn Query>> = Vec::new();
 
-        // Otros folders del Home
-        let folders_dir = dirs::home_dir().unwrap();
-        for folder in folders {
-            let folder_dir = folders_dir.join(folder);
-            let _ = tokio::spawn(async move { let _ = async_watch(folder_dir).await; });
+    if !parsed.exts.is_empty() {
+        let exts: Vec<&str> = parsed.exts.iter().map(|e| e.as_str()).collect();
+        filters.push(any_term(fields.extension, &exts));
+    }
+    if !parsed.kinds.is_empty() {
+        let mut should: Vec<(Occur, Box<dyn Query>)> = vec![(
+            Occur::Should,
+            any_term(fields.extension, &parsed.kind_extensions()),
+        )];
+        if parsed.wants_folders() {
+            should.push((Occur::Should, any_term(fields.kind, &[EntryKind::Dir.as_str()])));
         }
+        filters.push(Box::new(BooleanQuery::new(should)));
     }
-    //hacemos un for para armar los otros índices si faltan
-    for folder in folders {
-        let folder_idx = idx_path.join(folder);
-        match Index::open_in_dir(&folder_idx) {
-            Ok(_) => {}
-            Err(_) => {
-                let _ = tokio::spawn(async move {
-                    // Creamos el índice en background (el watcher ya está mirando el FS)
-                    let _ = create_index(folder).await;
-                });
-            }
+    if !parsed.dirs.is_empty() {
+        let should = parsed
+            .dirs
+            .iter()
+            .filter_map(|d| dir_query(fields, d))
+            .map(|q| (Occur::Should, q))
+            .collect();
+        filters.push(Box::new(BooleanQuery::new(should)));
+    }
+    if parsed.modified_after.is_some() || parsed.modified_before.is_some() {
+        let bound = |v: Option<i64>| match v {
+            Some(v) => Bound::Included(Term::from_field_i64(fields.mtime, v)),
+            None => Bound::Unbounded,
         };
+        filters.push(Box::new(RangeQuery::new(
+            bound(parsed.modified_after),
+            bound(parsed.modified_before),
+        )));
     }
-
-    //hacemos primero el de Desktop
-    let mut results = search_in_index(&index, 5).unwrap();
-
-    // Definir el score mínimo para considerar un resultado "excelente"
-    // let excellent_score_threshold = 8.0; // Ajusta este valor según tus necesidades
-
-    // Encontrar el mejor score actual
-    // let best_score = results
-    //     .iter()
-    //     .map(|(_, _, score)| *score)
-    //     .fold(0.0, f32::max);
-
-    //si esta incompleto Y no tenemos un resultado excelente, completar con los otros folders
-    // if results.len() < 15 && best_score < excellent_score_threshold {
-    for folder in folders {
-        let folder_idx = idx_path.join(folder);
-        if folder_idx.exists() {
-            match Index::open_in_dir(&folder_idx) {
-                Ok(idx) => {
-                    let mut new_result = search_in_index(&idx, 5)?;
-                    results.append(&mut new_result);
-                }
-                Err(_) => {}
-            }
-
-            // Actualizar el mejor score después de agregar nuevos resultados
-            // let current_best_score = results
-            //     .iter()
-            //     .map(|(_, _, score)| *score)
-            //     .fold(0.0, f32::max);
-
-            // if results.len() >= 15 || current_best_score >= excellent_score_threshold {
-            // break;
-            // }
-        }
-
-        // }
+    if parsed.size_min.is_some() || parsed.size_max.is_some() {
+        let bound = |v: Option<u64>| match v {
+            Some(v) => Bound::Included(Term::from_field_u64(fields.size, v)),
+            None => Bound::Unbounded,
+        };
+        filters.push(Box::new(RangeQuery::new(
+            bound(parsed.size_min),
+            bound(parsed.size_max),
+        )));
     }
-
-    results.sort_by(|a, b| b.2.partial_cmp(&a.2).unwrap());
-    results.truncate(15);
-
-    Ok(results)
+    filters
 }
 
-// Nueva función para scoring contextual
-fn calculate_contextual_score(name: &str, path: &str, base_score: f32, query: &str) -> f32 {
-    let mut score = base_score;
-
-    let q = query.to_lowercase();
-    let name_l = name.to_lowercase();
-    let path_l = path.to_lowercase();
-
-    // Prioridad: substring (regex-like) en el path por encima del fuzzy
-    if path_l.contains(&q) {
-        // Alto boost para coincidencia directa en el path completo
-        score *= 3.0;
-    } else if name_l.contains(&q) {
-        // Boost moderado si coincide en el nombre
-        score *= 1.5;
-    }
+// Nueva función para scoring contextual. `name_match`/`path_match` son los matches
+// fuzzy de la query sobre el nombre y el path completo. Los pesos salen del perfil de
+// ranking y cada factor queda anotado en el desglose
+fn calculate_contextual_score(
+    name: &str,
+    path: &str,
+    base_score: f32,
+    query: &str,
+    kind: EntryKind,
+    name_match: Option<&FuzzyMatch>,
+    path_match: Option<&FuzzyMatch>,
+) -> Explanation {
+    let profile = &settings::settings().ranking;
+    let mut score = Explanation::new(base_score);
+    let query_len = query.split_whitespace().map(|w| w.chars().count()).sum();
+
+    // Cuanto mejor la subsecuencia (al principio de palabras, seguida), más sube;
+    // en el nombre pesa más que repartida por las carpetas del path
+    let name_quality = name_match.map_or(0.0, |m| m.quality(query_len));
+    let path_quality = path_match.map_or(0.0, |m| m.quality(query_len));
+    score.apply(
+        "fuzzy",
+        1.0 + profile.name_match_weight * name_quality.max(path_quality * profile.path_match_factor),
+    );
 
-    // A futuro, conseguir el el ultimo uso
-    // Si falla (archivo borrado o sin metadata), no penalizamos
-    score *= get_recency_boost(path).unwrap_or(1.0);
+    // Lo que se abre seguido desde Aleph (el atime no sirve con noatime/relatime)
+    score.apply("frecency", frecency::boost(path));
 
     // Penalizar archivos en directorios muy profundos
     let depth = path.matches('/').count();
-    if depth > 6 {
-        score *= 0.8;
-    }
+    let deep = depth > profile.deep_path_depth;
+    score.apply("depth", if deep { profile.deep_path_penalty } else { 1.0 });
+
+    // Una carpeta cuyo nombre coincide suele ser lo que se busca (abrir un proyecto);
+    // sin contenido ni extensión, compensa con un boost propio
+    let dir_match = kind == EntryKind::Dir && name_match.is_some();
+    score.apply("dir", if dir_match { profile.dir_boost } else { 1.0 });
 
     // Boost para tipos de archivo comunes
-    if name.ends_with(".txt") || name.ends_with(".pdf") || name.ends_with(".doc") {
-        score *= 1.2;
-    }
+    let preferred = profile
+        .boosted_extensions
+        .iter()
+        .any(|ext| name.ends_with(&format!(".{}", ext)));
+    score.apply("extension", if preferred { profile.extension_boost } else { 1.0 });
 
     score
 }
 
-const HALF_LIFE_DAYS: f32 = 30.0; // ajusta a tu gusto
-const SECS_IN_DAY: f32 = 86_400.0;
-const MIN: f32 = 0.2;
-const MAX: f32 = 1.2;
-
-fn get_recency_boost(path: &str) -> Result<f32, String> {
-    let meta = fs::metadata(path).map_err(|e| e.to_string())?;
-    let last_access_time = meta.atime();
-    let half_life_secs = HALF_LIFE_DAYS * SECS_IN_DAY;
-    let now_secs = chrono::Utc::now().timestamp();
-
-    let age = (now_secs - last_access_time) as f32;
-    let base: f32 = 0.5;
-
-    let ans = base.powf(age / half_life_secs);
-
-    Ok(MIN + (MAX - MIN) * ans)
-    //ahora los criterios
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -362,7 +1012,7 @@ mod tests {
 
         //creo bien el index, pero encuentra cosas?
         let search = rt.block_on(async {
-            match search_index("leetcode.c").await {
+            match search_index("leetcode.c", None, None, None, None).await {
                 Ok(top) => top,
                 Err(e) => panic!("Error al buscar: {:?}", e),
             }
@@ -373,8 +1023,49 @@ mod tests {
 
         assert!(search
             .iter()
-            .any(|(a, b, _, _)| *a == "leetcode.c".to_string()
-                && *b == "/Users/bautistapessagno/Desktop/leetcode.c".to_string()));
+            .any(|hit| hit.title == "leetcode.c"
+                && hit.path == "/Users/bautistapessagno/Desktop/leetcode.c"));
+    }
+
+    #[test]
+    fn reconcile_finds_new_changed_and_missing() {
+        let file = |mtime, size| Stamp {
+            kind: EntryKind::File,
+            mtime: Some(mtime),
+            size: Some(size),
+        };
+        let indexed = HashMap::from([
+            ("/r/same.txt".to_string(), file(10, 5)),
+            ("/r/edited.txt".to_string(), file(10, 5)),
+            ("/r/gone.txt".to_string(), file(10, 5)),
+        ]);
+        let on_disk = vec![
+            (PathBuf::from("/r/same.txt"), file(10, 5)),
+            (PathBuf::from("/r/edited.txt"), file(20, 7)),
+            (PathBuf::from("/r/new.txt"), file(30, 1)),
+        ];
+
+        let diff = reconcile(indexed, on_disk);
+        assert_eq!(
+            diff.stale,
+            vec![
+                (PathBuf::from("/r/edited.txt"), EntryKind::File),
+                (PathBuf::from("/r/new.txt"), EntryKind::File),
+            ]
+        );
+        assert_eq!(diff.missing, vec!["/r/gone.txt".to_string()]);
+    }
+
+    #[test]
+    fn watcher_skips_hidden_and_aleph_paths() {
+        let home = dirs::home_dir().unwrap();
+        assert!(watchable(&home, &home.join("docs/informe.pdf")));
+        assert!(!watchable(&home, &home.join("docs/.informe.pdf.swp")));
+        assert!(!watchable(&home, &home.join(".git/docs/informe.pdf")));
+        assert!(!watchable(&home, &index_dir().unwrap().join("meta.json")));
+        assert!(!watchable(&home, &home.join(".config/aleph/ignore")));
+        // Una raíz oculta se indexa igual; lo que se mira es lo que está debajo
+        assert!(watchable(&home.join(".notas"), &home.join(".notas/ideas.md")));
     }
 }
 
@@ -383,7 +1074,7 @@ use futures::{
     SinkExt, StreamExt,
 };
 use notify::{Config, Event, RecommendedWatcher, RecursiveMode, Watcher};
-use notify::event::{EventKind, CreateKind};
+use notify::event::{EventKind, ModifyKind};
 
 // watcher para updates de cambios en los directorios
 fn async_watcher() -> notify::Result<(RecommendedWatcher, Receiver<notify::Result<Event>>)> {
@@ -419,44 +1110,88 @@ async fn async_watch<P: AsRef<Path>>(path: P) -> notify::Result<()> {
     static DEDUP_CACHE: OnceLock<Mutex<std::collections::HashMap<String, Instant>>> = OnceLock::new();
     const DEDUP_TTL: Duration = Duration::from_millis(500);
 
-    while let Some(res) = rx.next().await {
+    // Las dos mitades de un rename pueden llegar en eventos separados
+    let mut renames = RenameTracker::default();
+
+    loop {
+        // Con un rename a medias se espera la otra mitad un rato; si no llega,
+        // el archivo se fue fuera de la raíz
+        let next = if renames.has_pending() {
+            match tokio::time::timeout(PAIR_WINDOW, rx.next()).await {
+                Ok(next) => next,
+                Err(_) => {
+                    if let Some(action) = renames.flush() {
+                        in_background(move || apply_rename(action)).await;
+                    }
+                    continue;
+                }
+            }
+        } else {
+            rx.next().await
+        };
+        let res = match next {
+            Some(res) => res,
+            None => break,
+        };
+
         match res {
             Ok(event) => {
-                if event.kind.is_create() || event.kind.is_remove() {
+                if let EventKind::Modify(ModifyKind::Name(mode)) = event.kind {
+                    for action in renames.push(mode, &event) {
+                        in_background(move || apply_rename(action)).await;
+                    }
+                } else if event.kind.is_create() || event.kind.is_remove() || event.kind.is_modify() {
                     for changed_path in event.paths {
-                        if changed_path.is_file() || event.kind.is_remove() {
+                        if changed_path.exists() || event.kind.is_remove() {
                             // Clave de desduplicación
                             let key = format!(
                                 "{}|{}",
                                 changed_path.display(),
-                                if event.kind.is_create() { "create" } else { "remove" }
+                                match event.kind {
+                                    EventKind::Create(_) => "create",
+                                    EventKind::Remove(_) => "remove",
+                                    _ => "modify",
+                                }
                             );
 
-                            let cache = DEDUP_CACHE.get_or_init(|| Mutex::new(std::collections::HashMap::new()));
-                            let mut map = cache.lock().unwrap();
-                            let now = Instant::now();
+                            let seen_recently = {
+                                let cache = DEDUP_CACHE.get_or_init(|| Mutex::new(std::collections::HashMap::new()));
+                                let mut map = cache.lock().unwrap();
+                                let now = Instant::now();
 
-                            // Limpieza de entradas viejas (lazy)
-                            map.retain(|_, t| now.duration_since(*t) <= DEDUP_TTL);
+                                // Limpieza de entradas viejas (lazy)
+                                map.retain(|_, t| now.duration_since(*t) <= DEDUP_TTL);
 
-                            // Si lo vimos hace muy poco, saltamos
-                            let seen_recently = map.get(&key).map(|t| now.duration_since(*t) <= DEDUP_TTL).unwrap_or(false);
+                                // Si lo vimos hace muy poco, saltamos
+                                let seen = map.get(&key).map(|t| now.duration_since(*t) <= DEDUP_TTL).unwrap_or(false);
+                                if !seen {
+                                    map.insert(key, now);
+                                }
+                                seen
+                            };
                             if seen_recently {
                                 continue;
                             }
-                            map.insert(key, now);
 
                             match &event.kind {
-                                EventKind::Create(CreateKind::File) => {
-                                    if let Err(e) = add_to_index(&changed_path) {
-                                        println!("Error adding to index: {}", e);
-                                    }
+                                // Archivos y carpetas nuevos, o cambios de contenido/metadatos:
+                                // el documento se vuelve a armar
+                                EventKind::Create(_) | EventKind::Modify(_) => {
+                                    in_background(move || {
+                                        if let Err(e) = add_to_index(&changed_path) {
+                                            println!("Error adding to index: {}", e);
+                                        }
+                                    })
+                                    .await;
                                 }
                                 // Handle any kind of remove event (file or generic)
                                 EventKind::Remove(_) => {
-                                    if let Err(e) = delete_from_index(&changed_path) {
-                                        println!("Error deleting from index: {}", e);
-                                    }
+                                    in_background(move || {
+                                        if let Err(e) = delete_from_index(&changed_path) {
+                                            println!("Error deleting from index: {}", e);
+                                        }
+                                    })
+                                    .await;
                                 }
                                 _ => {}
                             }
@@ -470,156 +1205,384 @@ async fn async_watch<P: AsRef<Path>>(path: P) -> notify::Result<()> {
         }
     }
 
+    // Un rename que quedó a medias al cortar el watcher
+    if let Some(action) = renames.flush() {
+        in_background(move || apply_rename(action)).await;
+    }
+
     Ok(())
 }
 
-fn add_to_index(file_path: &Path) -> Result<(), String> {
-    // Determinar a qué índice pertenece
-    let (_folder_name, idx_dir) = match infer_folder_and_index_dir(file_path) {
-        Some(v) => v,
-        None => return Ok(()),
+// Lo que hace un evento en el índice es bloqueante (extraer el contenido, recorrer una
+// carpeta movida): corre fuera de los hilos del runtime, y se espera para mantener el
+// orden de los eventos
+async fn in_background(job: impl FnOnce() + Send + 'static) {
+    if let Err(e) = tokio::task::spawn_blocking(job).await {
+        println!("Error en el watcher: {}", e);
+    }
+}
+
+// Watchers activos, uno por raíz: abortar la tarea suelta el watcher y deja de mirarla
+fn watched_roots() -> &'static Mutex<HashMap<PathBuf, tokio::task::AbortHandle>> {
+    static WATCHED: OnceLock<Mutex<HashMap<PathBuf, tokio::task::AbortHandle>>> = OnceLock::new();
+    WATCHED.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+// Raíces cuyo índice se está armando en background
+fn indexing_roots() -> &'static Mutex<HashSet<PathBuf>> {
+    static INDEXING: OnceLock<Mutex<HashSet<PathBuf>>> = OnceLock::new();
+    INDEXING.get_or_init(|| Mutex::new(HashSet::new()))
+}
+
+// Raíces ya indexadas que se pusieron al día con el disco en esta ejecución
+fn reconciled_roots() -> &'static Mutex<HashSet<PathBuf>> {
+    static RECONCILED: OnceLock<Mutex<HashSet<PathBuf>>> = OnceLock::new();
+    RECONCILED.get_or_init(|| Mutex::new(HashSet::new()))
+}
+
+// Si lo que se busca (una raíz, o todas) todavía no tiene nada para devolver porque se
+// está indexando por primera vez. Con alguna raíz ya indexada se busca igual en esa
+fn first_build_running(file_index: &FileIndex, root: Option<&str>) -> bool {
+    let indexed = indexed_roots(file_index);
+    let indexing = indexing_roots().lock().unwrap().clone();
+    let keys: Vec<(String, bool)> = roots::roots()
+        .iter()
+        .map(|r| (root_key(r), indexing.contains(&r.path)))
+        .filter(|(key, _)| root.is_none_or(|root| root == key))
+        .collect();
+    let building = keys.iter().any(|(key, running)| *running && !indexed.contains(key));
+    building && keys.iter().all(|(key, _)| !indexed.contains(key))
+}
+
+// Arranca (una sola vez) el watcher de la raíz e indexa la raíz si todavía no se hizo;
+// si ya estaba indexada, la reconcilia con el disco la primera vez que se la ve
+pub fn spawn_root(root: IndexRoot) {
+    {
+        let mut watched = watched_roots().lock().unwrap();
+        if !watched.contains_key(&root.path) {
+            let watch_path = root.path.clone();
+            let task = tokio::spawn(async move {
+                let _ = async_watch(&watch_path).await;
+                watched_roots().lock().unwrap().remove(&watch_path);
+            });
+            watched.insert(root.path.clone(), task.abort_handle());
+        }
+    }
+
+    if cleared_roots().lock().unwrap().contains(&root.path) {
+        return;
+    }
+    let first_seen = reconciled_roots().lock().unwrap().insert(root.path.clone());
+    let indexed = match file_index() {
+        Ok(file_index) => indexed_roots(&file_index).contains(&root_key(&root)),
+        Err(_) => false,
     };
+    if indexed && !first_seen {
+        return;
+    }
+    spawn_indexing(root, false);
+}
 
-    let (index, path_f, filename_f, ext_f) = open_or_create_index(&idx_dir)?;
+// Indexa la raíz en background (el watcher ya está mirando el FS), de a una tarea por
+// raíz: devuelve false si ya había una. Sin `rebuild`, una raíz ya indexada se reconcilia
+fn spawn_indexing(root: IndexRoot, rebuild: bool) -> bool {
+    if !indexing_roots().lock().unwrap().insert(root.path.clone()) {
+        return false;
+    }
+    tokio::spawn(async move {
+        let _guard = bulk_lock().lock().await;
+        // Se vuelve a mirar con el lock tomado: una migración puede haber cambiado el índice en uso
+        let result = match file_index() {
+            Ok(file_index) => {
+                let reconcile = !rebuild && indexed_roots(&file_index).contains(&root_key(&root));
+                let kind = if reconcile { JobKind::Reconcile } else { JobKind::Index };
+                let job = status::start_job(kind, root_key(&root));
+                let result = if reconcile {
+                    reconcile_root(&root, &job).await
+                } else {
+                    create_index(&file_index, &root, &job).await
+                };
+                job.finish(&result);
+                result
+            }
+            Err(e) => Err(e.to_string()),
+        };
+        if let Err(e) = result {
+            println!("Error indexing {:?}: {}", root.path, e);
+        }
+        indexing_roots().lock().unwrap().remove(&root.path);
+    });
+    true
+}
 
-    let mut writer: IndexWriter = index
-        .writer_with_num_threads(2, 50_000_000)
-        .map_err(|e| e.to_string())?;
+// Olvida la raíz (al quitarla o al cambiarle las reglas): deja de mirarla ya, y lo
+// que se sabía de ella en esta ejecución no vale para la próxima vez que se agregue
+pub async fn drop_root_index(root: &IndexRoot) -> Result<(), String> {
+    if let Some(watcher) = watched_roots().lock().unwrap().remove(&root.path) {
+        watcher.abort();
+    }
+    reconciled_roots().lock().unwrap().remove(&root.path);
+    cleared_roots().lock().unwrap().remove(&root.path);
+    clear_root_docs(root).await
+}
+
+// Saca del índice todos los documentos de una raíz
+async fn clear_root_docs(root: &IndexRoot) -> Result<(), String> {
+    let _guard = bulk_lock().lock().await;
+    let file_index = file_index()?;
+
+    let writer = &file_index.writer;
+    writer.delete(Term::from_field_text(file_index.fields.root, &root_key(root)))?;
+    writer.commit().await?;
 
-    let absolute_path = if file_path.is_absolute() {
-        file_path.to_path_buf()
+    set_root_indexed(&file_index, root, false)
+}
+
+// Raíces vaciadas a mano: no se vuelven a indexar solas hasta un rebuild_root
+fn cleared_roots() -> &'static Mutex<HashSet<PathBuf>> {
+    static CLEARED: OnceLock<Mutex<HashSet<PathBuf>>> = OnceLock::new();
+    CLEARED.get_or_init(|| Mutex::new(HashSet::new()))
+}
+
+// Rearma la raíz desde cero en background (el avance se ve en index_status)
+#[tauri::command]
+pub async fn rebuild_root(path: String) -> Result<(), AlephError> {
+    let root = roots::find_root(&path)?;
+    cleared_roots().lock().unwrap().remove(&root.path);
+    if !spawn_indexing(root.clone(), true) {
+        return Err(AlephError::Busy(format!("la raíz {:?} ya se está indexando", root.path)));
+    }
+    Ok(())
+}
+
+// Saca del índice los documentos de la raíz sin volver a indexarla
+#[tauri::command]
+pub async fn clear_root(path: String) -> Result<(), AlephError> {
+    let root = roots::find_root(&path)?;
+    cleared_roots().lock().unwrap().insert(root.path.clone());
+    Ok(clear_root_docs(&root).await?)
+}
+
+// Junta los segmentos en uno y libera lo que ocupaban los documentos borrados.
+// Los segmentos son del índice entero (todas las raíces comparten índice)
+#[tauri::command]
+pub async fn compact_index() -> Result<(), AlephError> {
+    // Espera a que termine la indexación masiva que esté en curso
+    let _guard = bulk_lock().lock().await;
+    let file_index = file_index()?;
+    let job = status::start_job(JobKind::Compact, schema::FILES.name);
+    let result = file_index.writer.compact().await;
+    job.finish(&result);
+    Ok(result?)
+}
+
+pub fn stats() -> Result<IndexStats, AlephError> {
+    let file_index = file_index()?;
+    Ok(status::index_stats(&schema::FILES, &file_index.reader, &file_index.dir))
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RootStats {
+    pub path: String,
+    pub docs: usize,
+    // Se indexó completa al menos una vez
+    pub indexed: bool,
+    pub indexing: bool,
+    pub cleared: bool,
+}
+
+pub fn root_stats() -> Result<Vec<RootStats>, AlephError> {
+    let file_index = file_index()?;
+    let manifest = indexed_roots(&file_index);
+    let indexing = indexing_roots().lock().unwrap().clone();
+    let cleared = cleared_roots().lock().unwrap().clone();
+
+    Ok(roots::roots()
+        .into_iter()
+        .map(|root| {
+            let key = root_key(&root);
+            RootStats {
+                docs: root_doc_count(&file_index, &key),
+                indexed: manifest.contains(&key),
+                indexing: indexing.contains(&root.path),
+                cleared: cleared.contains(&root.path),
+                path: key,
+            }
+        })
+        .collect())
+}
+
+fn root_doc_count(file_index: &FileIndex, root_str: &str) -> usize {
+    let query = TermQuery::new(
+        Term::from_field_text(file_index.fields.root, root_str),
+        IndexRecordOption::Basic,
+    );
+    file_index
+        .reader
+        .searcher()
+        .search(&query, &Count)
+        .unwrap_or(0)
+}
+
+fn absolute(path: &Path) -> PathBuf {
+    if path.is_absolute() {
+        path.to_path_buf()
     } else {
-        std::env::current_dir().unwrap().join(file_path)
+        std::env::current_dir().unwrap().join(path)
+    }
+}
+
+// Para eventos sueltos del watcher: qué es el path y si las reglas de su raíz
+// (include/exclude/max_depth y archivos de ignore) lo dejan entrar al índice
+fn accepted_kind(filter: &RootFilter, path: &Path) -> Option<EntryKind> {
+    if !watchable(&filter.root.path, path) {
+        return None;
+    }
+    let kind = if path.is_dir() {
+        EntryKind::Dir
+    } else if path.is_file() {
+        EntryKind::File
+    } else {
+        return None;
     };
+    let accepted = match kind {
+        EntryKind::Dir => !filter.is_excluded(path),
+        EntryKind::File => filter.accepts_file(path),
+    };
+    if !accepted || excludes::is_ignored(&filter.root.path, path, kind == EntryKind::Dir) {
+        return None;
+    }
+    Some(kind)
+}
 
-    let path_str = absolute_path.display().to_string();
-    let name = absolute_path
-        .file_name()
-        .and_then(|s| s.to_str())
-        .unwrap_or("");
-    let ext = absolute_path
-        .extension()
-        .and_then(|s| s.to_str())
-        .unwrap_or("")
-        .to_lowercase();
-
-    let document = doc!(
-        path_f => path_str,
-        filename_f => name,
-        ext_f => ext.as_str(),
-    );
-    writer.add_document(document).map_err(|e| e.to_string())?;
-    writer.commit().map_err(|e| e.to_string())?;
-    writer
-        .wait_merging_threads()
-        .map_err(|e| e.to_string())?;
-    Ok(())
+// Carpetas de Aleph (los índices en ~/.cache/aleph y la config en ~/.config/aleph).
+// Nunca se indexan: cada commit toca los archivos del índice, y si el watcher los
+// tomara cada commit dispararía otro
+fn aleph_dirs() -> &'static [PathBuf] {
+    static DIRS: OnceLock<Vec<PathBuf>> = OnceLock::new();
+    DIRS.get_or_init(|| {
+        let mut found = Vec::new();
+        if let Ok(index_dir) = index_dir() {
+            found.extend(index_dir.parent().map(Path::to_path_buf));
+        }
+        if let Some(home) = dirs::home_dir() {
+            found.push(home.join(".config/aleph"));
+        }
+        found
+    })
 }
 
-fn delete_from_index(file_path: &Path) -> Result<(), String> {
-    let (_, idx_dir) = match infer_folder_and_index_dir(file_path) {
-        Some(v) => v,
-        None => return Ok(()),
+// Si un evento del watcher sobre `path` puede tocar el índice: como en el recorrido
+// (skip_hidden), nada oculto debajo de la raíz (.foo.swp, .~lock.x.docx#, carpetas
+// con punto), y nada de Aleph
+fn watchable(root: &Path, path: &Path) -> bool {
+    let hidden = match path.strip_prefix(root) {
+        Ok(rel) => rel
+            .components()
+            .any(|c| c.as_os_str().to_string_lossy().starts_with('.')),
+        Err(_) => true,
     };
+    !hidden && !aleph_dirs().iter().any(|dir| path.starts_with(dir))
+}
 
-    let (index, path_f, _filename_f, _ext_f) = open_or_create_index(&idx_dir)?;
-    let mut writer: IndexWriter = index
-        .writer_with_num_threads(2, 50_000_000)
-        .map_err(|e| e.to_string())?;
+// Un path del watcher que pertenece a una raíz y se puede tocar
+fn watched_path(path: &Path) -> Option<PathBuf> {
+    let path = absolute(path);
+    let filter = roots::filter_for_path(&path)?;
+    watchable(&filter.root.path, &path).then_some(path)
+}
 
-    let absolute_path = if file_path.is_absolute() {
-        file_path.to_path_buf()
-    } else {
-        std::env::current_dir().unwrap().join(file_path)
+// Alta o actualización (contenido/metadatos) de un solo archivo o carpeta
+fn add_to_index(file_path: &Path) -> Result<(), String> {
+    // Determinar a qué raíz pertenece
+    let filter = match roots::filter_for_path(file_path) {
+        Some(v) => v,
+        None => return Ok(()),
+    };
+    let absolute_path = absolute(file_path);
+    let kind = match accepted_kind(&filter, &absolute_path) {
+        Some(kind) => kind,
+        None => return Ok(()),
     };
 
-    // Canonicalizar para evitar diferencias de representación del path
+    let file_index = file_index()?;
+    let fields = file_index.fields;
+
+    // Si ya estaba (evento repetido o modificación) se reemplaza. El documento se arma
+    // acá, fuera del hilo del writer, que solo lo agrega
     let path_str = absolute_path.display().to_string();
-    // Borrar el documento exacto
-    let term = Term::from_field_text(path_f, &path_str);
-    writer.delete_term(term);
-
-    // Nota: si se elimina un directorio, este borrado exacto no elimina hijos.
-    // Podemos ampliar luego a borrar por prefijo usando una query compatible con la versión de Tantivy.
-    writer.commit().map_err(|e| e.to_string())?;
-    writer
-        .wait_merging_threads()
-        .map_err(|e| e.to_string())?;
-    Ok(())
+    let document = build_doc(&fields, &absolute_path, &root_key(&filter.root), kind);
+    file_index
+        .writer
+        .upsert(Term::from_field_text(fields.path, &path_str), document)
 }
 
-fn infer_folder_and_index_dir(file_path: &Path) -> Option<(String, PathBuf)> {
-    let home = dirs::home_dir()?;
+// Un rename/move en un solo commit: sale `from` y entra `to` (con todo lo que tenga
+// adentro si es una carpeta). Cualquiera de los dos lados puede faltar si el movimiento
+// cruza el borde de lo que miramos
+fn move_in_index(from: Option<&Path>, to: Option<&Path>) -> Result<(), String> {
+    let file_index = file_index()?;
+    let fields = file_index.fields;
 
-    // Construir pares (nombre_visible, ruta_absoluta)
-    let mut candidates: Vec<(String, PathBuf)> = Vec::new();
-    if let Some(desktop_dir) = dirs::desktop_dir() {
-        candidates.push(("Desktop".to_string(), desktop_dir));
-    }
-    for name in [
-        "Documents",
-        "Downloads",
-        "Pictures",
-        "Music",
-        "Movies",
-        "Library",
-        "Public",
-    ] {
-        candidates.push((name.to_string(), home.join(name)));
+    let mut changes = Vec::new();
+    if let Some(from) = from.and_then(watched_path) {
+        changes.extend(delete_subtree(&fields, &from));
     }
 
-    // Buscar el primer candidato cuyo path sea prefijo del file_path
-    for (display, abs_dir) in candidates {
-        if file_path.starts_with(&abs_dir) {
-            let idx_dir = home.join(".cache/aleph/index").join(&display);
-            return Some((display, idx_dir));
+    if let Some(to) = to.and_then(watched_path) {
+        if let Some(filter) = roots::filter_for_path(&to) {
+            let root_str = root_key(&filter.root);
+            let mut upsert = |path: &Path, kind: EntryKind| {
+                let key = Term::from_field_text(fields.path, &path.display().to_string());
+                let doc = build_doc(&fields, path, &root_str, kind);
+                changes.push(Change::Upsert(key, doc));
+            };
+            match accepted_kind(&filter, &to) {
+                Some(EntryKind::File) => upsert(&to, EntryKind::File),
+                Some(EntryKind::Dir) => {
+                    for entry in walk(&filter, &to).into_iter().flatten() {
+                        let path = entry.path();
+                        if let Some(kind) = entry_kind(&filter, &path, entry.file_type()) {
+                            upsert(&path, kind);
+                        }
+                    }
+                }
+                None => {}
+            }
         }
     }
 
-    None
+    file_index.writer.apply(changes)
+}
+
+fn apply_rename(action: RenameAction) {
+    let result = match &action {
+        RenameAction::Moved { from, to } => move_in_index(Some(from), Some(to)),
+        RenameAction::Removed(from) => move_in_index(Some(from), None),
+        RenameAction::Added(to) => move_in_index(None, Some(to)),
+    };
+    if let Err(e) = result {
+        println!("Error applying rename {:?}: {}", action, e);
+    }
 }
 
-#[allow(dead_code)]
-fn watched_folders() -> Vec<&'static str> {
+// Borra el documento del path y el de todo lo que cuelgue de él
+fn delete_subtree(fields: &FileFields, path: &Path) -> Vec<Change> {
+    let path_str = path.display().to_string();
     vec![
-        "Documents",
-        "Downloads",
-        "Pictures",
-        "Music",
-        "Movies",
-        "Library",
-        "Public",
+        Change::Delete(Term::from_field_text(fields.path, &path_str)),
+        Change::Delete(Term::from_field_text(fields.ancestors, &path_str)),
     ]
 }
 
-fn open_or_create_index(idx_dir: &Path) -> Result<(Index, Field, Field, Field), String> {
-    if !idx_dir.exists() {
-        fs::create_dir_all(idx_dir).map_err(|e| e.to_string())?;
-    }
-
-    // Definir el mismo schema que en create_index
-    let mut schema_builder = Schema::builder();
-    let _ = schema_builder.add_text_field("path", STORED | STRING);
-    let _ = schema_builder.add_text_field("filename", TEXT | STORED);
-    let _ = schema_builder.add_text_field("extension", STRING | STORED);
-    let schema = schema_builder.build();
-
-    let index = match Index::open_in_dir(idx_dir) {
-        Ok(idx) => idx,
-        Err(_) => Index::create_in_dir(idx_dir, schema).map_err(|e| e.to_string())?,
+fn delete_from_index(file_path: &Path) -> Result<(), String> {
+    let Some(path) = watched_path(file_path) else {
+        return Ok(());
     };
 
-    // Volver a obtener los fields desde el schema real del índice
-    let s = index.schema();
-    let path_f = s
-        .get_field("path")
-        .map_err(|_| "field path not found".to_string())?;
-    let filename_f = s
-        .get_field("filename")
-        .map_err(|_| "field filename not found".to_string())?;
-    let ext_f = s
-        .get_field("extension")
-        .map_err(|_| "field extension not found".to_string())?;
-
-    Ok((index, path_f, filename_f, ext_f))
+    // Si era una carpeta se va con todo lo que tenía adentro, en el mismo commit
+    let file_index = file_index()?;
+    file_index
+        .writer
+        .apply(delete_subtree(&file_index.fields, &path))
 }
@@ -0,0 +1,181 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
+
+use crate::clicks;
+use crate::error::AlephError;
+use crate::settings;
+
+// Registro de lo que se abre desde Aleph (archivos, carpetas y apps), en
+// ~/.config/aleph/launches.json. De ahí sale la "frecencia": cuántas veces y qué tan
+// recientemente se abrió algo, para subirlo en los resultados (los pesos están en el
+// perfil de ranking de settings.json)
+const SECS_IN_DAY: f64 = 86_400.0;
+// Se guardan las entradas más usadas hasta este tope
+const MAX_ENTRIES: usize = 2_000;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct Launch {
+    pub count: u32,
+    // Segundos unix de la última vez que se abrió
+    pub last_launched: i64,
+    // Frecencia a la fecha de `last_launched`: cada apertura suma 1 y lo acumulado se
+    // va dividiendo a la mitad cada `frecency_half_life_days`
+    score: f64,
+}
+
+impl Launch {
+    fn decay(&self, now: i64) -> f64 {
+        let age_days = (now - self.last_launched).max(0) as f64 / SECS_IN_DAY;
+        0.5f64.powf(age_days / settings::settings().ranking.frecency_half_life_days)
+    }
+
+    pub fn frecency(&self, now: i64) -> f64 {
+        self.score * self.decay(now)
+    }
+
+    pub fn record(&mut self, now: i64) {
+        self.score = self.frecency(now) + 1.0;
+        self.count += 1;
+        self.last_launched = now;
+    }
+}
+
+fn launches_path() -> Option<PathBuf> {
+    Some(dirs::home_dir()?.join(".config/aleph/launches.json"))
+}
+
+fn load_launches() -> HashMap<String, Launch> {
+    let raw = match launches_path().and_then(|p| fs::read_to_string(p).ok()) {
+        Some(raw) => raw,
+        None => return HashMap::new(),
+    };
+    match serde_json::from_str(&raw) {
+        Ok(launches) => launches,
+        Err(e) => {
+            println!("launches.json inválido ({}), empezando de cero", e);
+            HashMap::new()
+        }
+    }
+}
+
+fn save_launches(launches: &HashMap<String, Launch>) -> Result<(), String> {
+    let path = launches_path().ok_or_else(|| "no home dir".to_string())?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let raw = serde_json::to_string(launches).map_err(|e| e.to_string())?;
+    fs::write(path, raw).map_err(|e| e.to_string())
+}
+
+// Se carga de disco la primera vez que se usa
+fn launches() -> &'static Mutex<HashMap<String, Launch>> {
+    static LAUNCHES: OnceLock<Mutex<HashMap<String, Launch>>> = OnceLock::new();
+    LAUNCHES.get_or_init(|| Mutex::new(load_launches()))
+}
+
+pub fn unix_now() -> i64 {
+    chrono::Utc::now().timestamp()
+}
+
+// Anota que se abrió `path`
+pub fn record(path: &str) -> Result<(), String> {
+    let now = unix_now();
+    let mut launches = launches().lock().unwrap();
+    launches.entry(path.to_string()).or_default().record(now);
+    if launches.len() > MAX_ENTRIES {
+        prune(&mut launches, now);
+    }
+    save_launches(&launches)
+}
+
+// Se queda con las MAX_ENTRIES de más frecencia
+fn prune(launches: &mut HashMap<String, Launch>, now: i64) {
+    let mut scores: Vec<f64> = launches.values().map(|l| l.frecency(now)).collect();
+    scores.sort_by(|a, b| b.total_cmp(a));
+    let cutoff = scores[MAX_ENTRIES - 1];
+    launches.retain(|_, l| l.frecency(now) >= cutoff);
+}
+
+// Multiplicador para el puntaje de un resultado: 1.0 si nunca se abrió, hasta
+// 1.0 + frecency_max_boost si se abre seguido
+pub fn boost(path: &str) -> f32 {
+    let launches = launches().lock().unwrap();
+    launches
+        .get(path)
+        .map_or(1.0, |launch| boost_for(launch.frecency(unix_now())))
+}
+
+fn boost_for(frecency: f64) -> f32 {
+    let profile = &settings::settings().ranking;
+    1.0 + profile.frecency_max_boost
+        * (frecency / (frecency + profile.frecency_half_boost_at)) as f32
+}
+
+// Olvida lo aprendido (aperturas y elecciones por query): de un path, o todo si no se
+// pasa ninguno
+#[tauri::command]
+pub fn reset_frecency(path: Option<String>) -> Result<(), AlephError> {
+    {
+        let mut launches = launches().lock().unwrap();
+        match &path {
+            Some(path) => {
+                launches.remove(path);
+            }
+            None => launches.clear(),
+        }
+        save_launches(&launches)?;
+    }
+    Ok(clicks::reset(path.as_deref())?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY: i64 = 86_400;
+
+    #[test]
+    fn frecency_grows_with_use_and_decays_with_time() {
+        let now = 1_700_000_000;
+        let mut launch = Launch::default();
+        launch.record(now);
+        launch.record(now);
+        assert_eq!(launch.count, 2);
+        assert!((launch.frecency(now) - 2.0).abs() < 1e-9);
+
+        // Al pasar la vida media vale la mitad
+        let half_life = settings::settings().ranking.frecency_half_life_days * DAY as f64;
+        let later = now + half_life as i64;
+        assert!((launch.frecency(later) - 1.0).abs() < 1e-9);
+        launch.record(later);
+        assert!((launch.frecency(later) - 2.0).abs() < 1e-9);
+
+        // Algo abierto una vez hace mucho casi no pesa
+        let mut old = Launch::default();
+        old.record(now - 10 * half_life as i64);
+        assert!(old.frecency(now) < 0.01);
+
+        assert_eq!(boost_for(0.0), 1.0);
+        assert!(boost_for(launch.frecency(later)) > boost_for(old.frecency(now)));
+        assert!(boost_for(1_000.0) <= 1.0 + settings::settings().ranking.frecency_max_boost);
+    }
+
+    #[test]
+    fn prunes_the_least_used() {
+        let now = 1_700_000_000;
+        let mut launches: HashMap<String, Launch> = (0..MAX_ENTRIES + 10)
+            .map(|i| {
+                let mut launch = Launch::default();
+                launch.record(now - i as i64 * DAY);
+                (format!("/tmp/{}", i), launch)
+            })
+            .collect();
+        prune(&mut launches, now);
+        assert_eq!(launches.len(), MAX_ENTRIES);
+        assert!(launches.contains_key("/tmp/0"));
+        assert!(!launches.contains_key(&format!("/tmp/{}", MAX_ENTRIES + 5)));
+    }
+}
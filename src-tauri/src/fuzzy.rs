@@ -0,0 +1,250 @@
+use serde::Serialize;
+
+use crate::tokenizer;
+
+// Scorer de subsecuencias al estilo fzf: los caracteres de la query tienen que
+// aparecer en orden en el texto, y pesa más cuando caen al principio de una palabra,
+// seguidos o al principio del texto. Se usa para reordenar los candidatos de tantivy
+const SCORE_MATCH: i32 = 16;
+const GAP_START: i32 = -3;
+const GAP_EXTENSION: i32 = -1;
+// Después de un espacio, de un separador de path o de otro carácter que no es de palabra
+const BONUS_BOUNDARY_WHITE: i32 = 10;
+const BONUS_BOUNDARY_DELIMITER: i32 = 9;
+const BONUS_BOUNDARY: i32 = 8;
+const BONUS_NON_WORD: i32 = 8;
+// camelCase y letra -> número
+const BONUS_CAMEL_123: i32 = BONUS_BOUNDARY + GAP_EXTENSION;
+// Un carácter seguido del anterior compensa lo que costaría un hueco
+const BONUS_CONSECUTIVE: i32 = -(GAP_START + GAP_EXTENSION);
+// El primer carácter de la query pesa doble
+const BONUS_FIRST_CHAR_MULTIPLIER: i32 = 2;
+// Extra para el principio del texto: un prefijo del nombre gana a una palabra del medio
+const BONUS_PREFIX: i32 = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CharClass {
+    White,
+    NonWord,
+    Delimiter,
+    Lower,
+    Upper,
+    Number,
+}
+
+fn char_class(c: char) -> CharClass {
+    if c.is_lowercase() {
+        CharClass::Lower
+    } else if c.is_uppercase() {
+        CharClass::Upper
+    } else if c.is_numeric() {
+        CharClass::Number
+    } else if c.is_alphabetic() {
+        // Letras sin mayúsculas (CJK, etc.)
+        CharClass::Lower
+    } else if c.is_whitespace() {
+        CharClass::White
+    } else if matches!(c, '/' | '\\' | ',' | ':' | ';' | '|') {
+        CharClass::Delimiter
+    } else {
+        CharClass::NonWord
+    }
+}
+
+fn bonus(prev: CharClass, cur: CharClass) -> i32 {
+    use CharClass::*;
+    match (prev, cur) {
+        (_, White | NonWord | Delimiter) => match prev {
+            White => BONUS_BOUNDARY_WHITE,
+            _ => BONUS_NON_WORD,
+        },
+        (White, _) => BONUS_BOUNDARY_WHITE,
+        (Delimiter, _) => BONUS_BOUNDARY_DELIMITER,
+        (NonWord, _) => BONUS_BOUNDARY,
+        (Lower, Upper) | (Lower | Upper, Number) => BONUS_CAMEL_123,
+        _ => 0,
+    }
+}
+
+// Resultado de matchear la query contra un texto. Los rangos son [inicio, fin) en
+// caracteres (no bytes) del texto original, para resaltarlos en la UI
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct FuzzyMatch {
+    pub score: i32,
+    pub ranges: Vec<(usize, usize)>,
+}
+
+impl FuzzyMatch {
+    // Qué tan bueno es el match entre 0 y 1, comparado con el mejor posible para una
+    // query de `query_len` caracteres (todos seguidos al principio del texto)
+    pub fn quality(&self, query_len: usize) -> f32 {
+        if query_len == 0 {
+            return 0.0;
+        }
+        let first = BONUS_BOUNDARY_WHITE + BONUS_PREFIX;
+        let best = query_len as i32 * (SCORE_MATCH + first) + first * (BONUS_FIRST_CHAR_MULTIPLIER - 1);
+        (self.score as f32 / best as f32).clamp(0.0, 1.0)
+    }
+}
+
+// Matchea cada palabra de la query por separado (todas tienen que estar), sin
+// distinguir mayúsculas ni acentos
+pub fn fuzzy_match(query: &str, text: &str) -> Option<FuzzyMatch> {
+    let chars: Vec<char> = text.chars().collect();
+    // Cada carácter plegado por separado, para no perder la correspondencia con el original
+    let folded: Vec<Option<char>> = chars
+        .iter()
+        .map(|c| tokenizer::fold(c.encode_utf8(&mut [0; 4])).chars().next())
+        .collect();
+    let bonuses: Vec<i32> = chars
+        .iter()
+        .enumerate()
+        .map(|(j, &c)| {
+            if j == 0 {
+                return BONUS_BOUNDARY_WHITE + BONUS_PREFIX;
+            }
+            bonus(char_class(chars[j - 1]), char_class(c))
+        })
+        .collect();
+
+    let mut result = FuzzyMatch::default();
+    let mut any = false;
+    for word in query.split_whitespace() {
+        let pattern: Vec<char> = tokenizer::fold(word).chars().collect();
+        if pattern.is_empty() {
+            continue;
+        }
+        let (score, positions) = match_word(&pattern, &folded, &bonuses)?;
+        result.score += score;
+        result.ranges.extend(positions.into_iter().map(|p| (p, p + 1)));
+        any = true;
+    }
+    if !any {
+        return None;
+    }
+    result.ranges = merge_ranges(result.ranges);
+    Some(result)
+}
+
+// Programación dinámica de fzf (v2): el mejor puntaje terminando la i-ésima letra de
+// la query en cada posición del texto, con de dónde vino para reconstruir las posiciones
+fn match_word(pattern: &[char], text: &[Option<char>], bonuses: &[i32]) -> Option<(i32, Vec<usize>)> {
+    let (m, n) = (pattern.len(), text.len());
+    if m > n {
+        return None;
+    }
+    const NONE: i32 = i32::MIN / 2;
+    // score[i][j]: el mejor puntaje con pattern[i] en text[j]
+    let mut score = vec![vec![NONE; n]; m];
+    // bonus del primer carácter de la corrida seguida que termina en (i, j)
+    let mut run_bonus = vec![vec![0; n]; m];
+    let mut from = vec![vec![usize::MAX; n]; m];
+
+    for i in 0..m {
+        // El mejor (puntaje + penalidad del hueco, origen) para llegar a j con un hueco
+        let mut gap: Option<(i32, usize)> = None;
+        for j in i..n {
+            if i > 0 && j >= 2 && score[i - 1][j - 2] > NONE {
+                let opened = score[i - 1][j - 2] + GAP_START;
+                gap = match gap {
+                    Some((best, k)) if best + GAP_EXTENSION >= opened => {
+                        Some((best + GAP_EXTENSION, k))
+                    }
+                    _ => Some((opened, j - 2)),
+                };
+            } else if let Some((best, k)) = gap {
+                gap = Some((best + GAP_EXTENSION, k));
+            }
+
+            if text[j] != Some(pattern[i]) {
+                continue;
+            }
+            if i == 0 {
+                score[0][j] = SCORE_MATCH + bonuses[j] * BONUS_FIRST_CHAR_MULTIPLIER;
+                run_bonus[0][j] = bonuses[j];
+                continue;
+            }
+
+            let mut best = NONE;
+            if j >= 1 && score[i - 1][j - 1] > NONE {
+                // Seguido del anterior: hereda el bonus del principio de la corrida
+                let run = run_bonus[i - 1][j - 1].max(BONUS_CONSECUTIVE);
+                best = score[i - 1][j - 1] + SCORE_MATCH + run.max(bonuses[j]);
+                run_bonus[i][j] = run;
+                from[i][j] = j - 1;
+            }
+            if let Some((gap_score, k)) = gap {
+                let candidate = gap_score + SCORE_MATCH + bonuses[j];
+                if candidate > best {
+                    best = candidate;
+                    run_bonus[i][j] = bonuses[j];
+                    from[i][j] = k;
+                }
+            }
+            score[i][j] = best;
+        }
+    }
+
+    let (end, &best) = score[m - 1]
+        .iter()
+        .enumerate()
+        .filter(|&(_, &s)| s > NONE)
+        .max_by_key(|&(j, &s)| (s, std::cmp::Reverse(j)))?;
+    let mut positions = vec![end; m];
+    for i in (1..m).rev() {
+        positions[i - 1] = from[i][positions[i]];
+    }
+    Some((best, positions))
+}
+
+// Junta posiciones sueltas y rangos que se tocan o se pisan
+fn merge_ranges(mut ranges: Vec<(usize, usize)>) -> Vec<(usize, usize)> {
+    ranges.sort_unstable();
+    let mut merged: Vec<(usize, usize)> = Vec::with_capacity(ranges.len());
+    for (start, end) in ranges {
+        match merged.last_mut() {
+            Some(last) if start <= last.1 => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ranges(query: &str, text: &str) -> Vec<(usize, usize)> {
+        fuzzy_match(query, text).unwrap().ranges
+    }
+
+    fn score(query: &str, text: &str) -> i32 {
+        fuzzy_match(query, text).map_or(0, |m| m.score)
+    }
+
+    #[test]
+    fn matches_subsequences_and_returns_ranges() {
+        assert_eq!(fuzzy_match("xyz", "MyReportFinal.docx"), None);
+        assert_eq!(ranges("repfin", "MyReportFinal.docx"), vec![(2, 5), (8, 11)]);
+        assert_eq!(ranges("report", "/docs/MyReportFinal"), vec![(8, 14)]);
+        // Varias palabras se matchean por separado
+        assert_eq!(ranges("final my", "MyReportFinal"), vec![(0, 2), (8, 13)]);
+        // Rangos en caracteres, sin acentos ni mayúsculas
+        assert_eq!(ranges("ANO", "año_2024.xlsx"), vec![(0, 3)]);
+    }
+
+    #[test]
+    fn prefers_boundaries_consecutive_and_prefixes() {
+        // Principio de palabra antes que en el medio
+        assert!(score("fin", "MyReportFinal") > score("fin", "Elfinder"));
+        // Seguidos antes que desparramados
+        assert!(score("rep", "report.txt") > score("rep", "ramp_eraser.txt"));
+        // Prefijo del nombre antes que una palabra del medio
+        assert!(score("notes", "notes-2024.md") > score("notes", "old notes.md"));
+
+        let exact = fuzzy_match("notes", "notes.md").unwrap();
+        let spread = fuzzy_match("notes", "no_tes.md").unwrap();
+        assert!(exact.quality(5) > spread.quality(5));
+        assert!(exact.quality(5) <= 1.0);
+    }
+}
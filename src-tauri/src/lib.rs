@@ -1,34 +1,96 @@
 // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
 
 mod apps;
+mod clicks;
+mod content;
+mod error;
+mod excludes;
 mod files;
+mod frecency;
+mod fuzzy;
 mod icons;
 mod llm;
+mod office;
+mod providers;
+mod query;
+mod ranking;
+mod renames;
+mod results;
+mod roots;
+mod schema;
+mod settings;
+mod status;
+mod tokenizer;
+mod writer;
 
 //opener
 use opener;
 
+use error::AlephError;
+use std::path::Path;
+
 #[tauri::command]
 fn greet(name: &str) -> String {
     format!("Hello, {}! You've been greeted from Rust!", name)
 }
 
 #[tauri::command]
-fn open_path(path: &str) -> Result<(), String> {
-    opener::open(path).map_err(|e| e.to_string())?;
+fn open_path(path: &str, query: Option<String>) -> Result<(), AlephError> {
+    if !Path::new(path).exists() {
+        return Err(AlephError::NotFound(path.to_string()));
+    }
+    opener::open(path).map_err(|e| AlephError::Io(e.to_string()))?;
+    // Lo que se abre (archivos y apps) sube en las próximas búsquedas, y más todavía
+    // en las que empiezan como la query con la que se encontró
+    if let Err(e) = frecency::record(path) {
+        println!("Error saving launch of {}: {}", path, e);
+    }
+    if let Some(query) = query.filter(|q| !q.trim().is_empty()) {
+        if let Err(e) = clicks::record(&query, path) {
+            println!("Error saving pick of {} for {:?}: {}", path, query, e);
+        }
+    }
     Ok(())
 }
 
+// Abre la carpeta que contiene a `path`
+#[tauri::command]
+fn reveal_path(path: &str) -> Result<(), AlephError> {
+    let parent = Path::new(path)
+        .parent()
+        .filter(|p| p.exists())
+        .ok_or_else(|| AlephError::NotFound(path.to_string()))?;
+    opener::open(parent).map_err(|e| AlephError::Io(e.to_string()))
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
+        .setup(|app| {
+            // Los trabajos de indexación avisan su avance con eventos
+            status::set_app_handle(app.handle().clone());
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             greet,
             files::search_index,
+            files::explain_search,
             open_path,
+            reveal_path,
             apps::app_search,
-            llm::llms
+            apps::explain_app_search,
+            providers::search,
+            llm::llms,
+            roots::list_roots,
+            roots::add_root,
+            roots::remove_root,
+            content::content_diagnostics,
+            status::index_status,
+            files::rebuild_root,
+            files::clear_root,
+            files::compact_index,
+            frecency::reset_frecency
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -1,4 +1,5 @@
 // Import required modules from the LLM library for Google Gemini integration
+use crate::error::AlephError;
 use llm::{
     builder::{LLMBackend, LLMBuilder}, // Builder pattern components
     chat::ChatMessage,                 // Chat-related structures
@@ -6,7 +7,7 @@ use llm::{
 
 #[tokio::main]
 #[tauri::command]
-pub async fn llms(query: &str) -> Result<String, String> {
+pub async fn llms(query: &str) -> Result<String, AlephError> {
     // Get Google API key from environment variable or use test key as fallback
     let api_key = std::env::var("OLLAMA_API_KEY").unwrap_or("ollama-key".into());
 
@@ -21,7 +22,7 @@ pub async fn llms(query: &str) -> Result<String, String> {
         // Optional: Set system prompt
         .system("You are a helpful AI assistant specialized in programming.")
         .build()
-        .expect("Failed to build LLM (Ollama)");
+        .map_err(|e| AlephError::Llm(e.to_string()))?;
 
     // Prepare conversation history with example messages
 
@@ -30,7 +31,7 @@ pub async fn llms(query: &str) -> Result<String, String> {
         .build()];
 
     // Send chat request and handle the response
-    let ans = llm.chat(&messages).await.map_err(|e| e.to_string())?;
+    let ans = llm.chat(&messages).await.map_err(|e| AlephError::Llm(e.to_string()))?;
 
     println!("respuesta: {ans}");
 
@@ -0,0 +1,312 @@
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use zip::ZipArchive;
+
+// Office (docx/xlsx/pptx) y OpenDocument (odt/ods/odp) son zips con XML adentro:
+// se lee el XML relevante de cada formato y se junta su texto
+pub const OFFICE_EXTENSIONS: &[&str] = &["docx", "xlsx", "pptx", "odt", "ods", "odp"];
+
+#[derive(Debug, Default, PartialEq)]
+pub struct OfficeDoc {
+    pub text: String,
+    pub title: Option<String>,
+    pub author: Option<String>,
+}
+
+pub fn extract_office(path: &Path, ext: &str, max_bytes: u64) -> Result<OfficeDoc, String> {
+    let file = File::open(path).map_err(|e| e.to_string())?;
+    let mut archive = ZipArchive::new(file).map_err(|e| e.to_string())?;
+
+    let mut doc = match ext {
+        "docx" => OfficeDoc {
+            text: xml_text(&read_entry(&mut archive, "word/document.xml", max_bytes)?, &["p"]),
+            ..Default::default()
+        },
+        "pptx" => {
+            let mut text = String::new();
+            for name in numbered_entries(&archive, "ppt/slides/slide") {
+                text.push_str(&xml_text(&read_entry(&mut archive, &name, max_bytes)?, &["p"]));
+                text.push('\n');
+            }
+            OfficeDoc {
+                text,
+                ..Default::default()
+            }
+        }
+        "xlsx" => {
+            // Los textos de las celdas están casi todos en sharedStrings; en las hojas
+            // quedan los números, fórmulas resueltas y strings inline
+            let mut text = match read_entry(&mut archive, "xl/sharedStrings.xml", max_bytes) {
+                Ok(xml) => xml_text(&xml, &["si"]),
+                Err(_) => String::new(),
+            };
+            for name in numbered_entries(&archive, "xl/worksheets/sheet") {
+                text.push('\n');
+                text.push_str(&sheet_values(&read_entry(&mut archive, &name, max_bytes)?));
+            }
+            OfficeDoc {
+                text,
+                ..Default::default()
+            }
+        }
+        "odt" | "ods" | "odp" => OfficeDoc {
+            text: xml_text(&read_entry(&mut archive, "content.xml", max_bytes)?, &["p", "h"]),
+            ..Default::default()
+        },
+        _ => return Err(format!("formato no soportado: {}", ext)),
+    };
+
+    // Metadatos: docProps/core.xml en Office, meta.xml en OpenDocument
+    let meta_entry = if ext.starts_with("od") {
+        "meta.xml"
+    } else {
+        "docProps/core.xml"
+    };
+    if let Ok(xml) = read_entry(&mut archive, meta_entry, max_bytes) {
+        doc.title = xml_element(&xml, &["title"]);
+        doc.author = xml_element(&xml, &["creator", "initial-creator"]);
+    }
+
+    Ok(doc)
+}
+
+// Lee una entrada del zip con un tope de tamaño (por si es una zip bomb)
+fn read_entry(archive: &mut ZipArchive<File>, name: &str, max_bytes: u64) -> Result<String, String> {
+    let entry = archive.by_name(name).map_err(|e| e.to_string())?;
+    let mut xml = String::new();
+    entry
+        .take(max_bytes)
+        .read_to_string(&mut xml)
+        .map_err(|e| e.to_string())?;
+    Ok(xml)
+}
+
+// Entradas `<prefix>N.xml` ordenadas por N (slide1, slide2, ..., slide10)
+fn numbered_entries(archive: &ZipArchive<File>, prefix: &str) -> Vec<String> {
+    let mut entries: Vec<(u32, String)> = archive
+        .file_names()
+        .filter_map(|name| {
+            let n = name.strip_prefix(prefix)?.strip_suffix(".xml")?.parse().ok()?;
+            Some((n, name.to_string()))
+        })
+        .collect();
+    entries.sort();
+    entries.into_iter().map(|(_, name)| name).collect()
+}
+
+// Todo el texto del XML; cada `block` que cierra agrega un salto de línea.
+// Los tabs y espacios explícitos (w:tab, text:s, text:tab) se respetan
+fn xml_text(xml: &str, blocks: &[&str]) -> String {
+    let mut reader = Reader::from_str(xml);
+    let mut text = String::new();
+    loop {
+        match reader.read_event() {
+            Ok(Event::Text(t)) => {
+                if let Ok(t) = t.unescape() {
+                    text.push_str(&t);
+                }
+            }
+            Ok(Event::CData(t)) => text.push_str(&String::from_utf8_lossy(&t)),
+            Ok(Event::Empty(e)) => match e.local_name().as_ref() {
+                b"tab" => text.push('\t'),
+                b"s" | b"br" | b"line-break" => text.push(' '),
+                _ => {}
+            },
+            Ok(Event::End(e)) => {
+                let name = e.local_name();
+                if blocks.iter().any(|b| b.as_bytes() == name.as_ref()) {
+                    text.push('\n');
+                }
+            }
+            Ok(Event::Eof) | Err(_) => break,
+            _ => {}
+        }
+    }
+    text
+}
+
+// Valores de las celdas de una hoja xlsx, salvo los índices a sharedStrings (t="s")
+fn sheet_values(xml: &str) -> String {
+    let mut reader = Reader::from_str(xml);
+    let mut text = String::new();
+    let mut shared_cell = false;
+    let mut in_value = false;
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e)) => match e.local_name().as_ref() {
+                b"c" => {
+                    shared_cell = e
+                        .attributes()
+                        .flatten()
+                        .any(|a| a.key.as_ref() == b"t" && a.value.as_ref() == b"s");
+                }
+                b"v" | b"t" => in_value = true,
+                _ => {}
+            },
+            Ok(Event::Text(t)) if in_value && !shared_cell => {
+                if let Ok(t) = t.unescape() {
+                    text.push_str(&t);
+                    text.push(' ');
+                }
+            }
+            Ok(Event::End(e)) => match e.local_name().as_ref() {
+                b"v" | b"t" => in_value = false,
+                b"row" => text.push('\n'),
+                _ => {}
+            },
+            Ok(Event::Eof) | Err(_) => break,
+            _ => {}
+        }
+    }
+    text
+}
+
+// Texto del primer elemento (por nombre local) que no esté vacío
+fn xml_element(xml: &str, names: &[&str]) -> Option<String> {
+    let mut reader = Reader::from_str(xml);
+    let mut inside = false;
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e)) => {
+                let name = e.local_name();
+                inside = names.iter().any(|n| n.as_bytes() == name.as_ref());
+            }
+            Ok(Event::Text(t)) if inside => {
+                let value = t.unescape().ok()?.trim().to_string();
+                if !value.is_empty() {
+                    return Some(value);
+                }
+            }
+            Ok(Event::End(_)) => inside = false,
+            Ok(Event::Eof) | Err(_) => return None,
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use zip::write::SimpleFileOptions;
+    use zip::ZipWriter;
+
+    fn write_zip(path: &Path, entries: &[(&str, &str)]) {
+        let mut zip = ZipWriter::new(File::create(path).unwrap());
+        for (name, body) in entries {
+            zip.start_file(*name, SimpleFileOptions::default()).unwrap();
+            zip.write_all(body.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap();
+    }
+
+    #[test]
+    fn extracts_docx_text_and_metadata() {
+        let path = std::env::temp_dir().join("aleph-office-test.docx");
+        write_zip(
+            &path,
+            &[
+                (
+                    "word/document.xml",
+                    r#"<w:document xmlns:w="w"><w:body><w:p><w:r><w:t>Factura</w:t></w:r><w:r><w:t xml:space="preserve"> marzo &amp; abril</w:t></w:r></w:p><w:p><w:r><w:t>Total</w:t></w:r></w:p></w:body></w:document>"#,
+                ),
+                (
+                    "docProps/core.xml",
+                    r#"<cp:coreProperties xmlns:cp="cp" xmlns:dc="dc"><dc:title>Facturas 2025</dc:title><dc:creator>Ana</dc:creator></cp:coreProperties>"#,
+                ),
+            ],
+        );
+
+        let doc = extract_office(&path, "docx", 1024 * 1024).unwrap();
+        assert_eq!(doc.text, "Factura marzo & abril\nTotal\n");
+        assert_eq!(doc.title.as_deref(), Some("Facturas 2025"));
+        assert_eq!(doc.author.as_deref(), Some("Ana"));
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn extracts_xlsx_cells() {
+        let path = std::env::temp_dir().join("aleph-office-test.xlsx");
+        write_zip(
+            &path,
+            &[
+                (
+                    "xl/sharedStrings.xml",
+                    r#"<sst><si><t>Cliente</t></si><si><t>Monto</t></si></sst>"#,
+                ),
+                (
+                    "xl/worksheets/sheet1.xml",
+                    r#"<worksheet><sheetData><row><c t="s"><v>0</v></c><c t="s"><v>1</v></c></row><row><c t="inlineStr"><is><t>ACME</t></is></c><c><v>1500</v></c></row></sheetData></worksheet>"#,
+                ),
+            ],
+        );
+
+        let doc = extract_office(&path, "xlsx", 1024 * 1024).unwrap();
+        assert!(doc.text.contains("Cliente"));
+        assert!(doc.text.contains("ACME"));
+        assert!(doc.text.contains("1500"));
+        // los índices a sharedStrings no son contenido
+        assert!(!doc.text.contains("0 1"));
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn extracts_pptx_slides_in_order_and_metadata() {
+        let path = std::env::temp_dir().join("aleph-office-test.pptx");
+        write_zip(
+            &path,
+            &[
+                (
+                    "ppt/slides/slide10.xml",
+                    r#"<p:sld xmlns:p="p" xmlns:a="a"><p:txBody><a:p><a:r><a:t>Cierre</a:t></a:r></a:p></p:txBody></p:sld>"#,
+                ),
+                (
+                    "ppt/slides/slide2.xml",
+                    r#"<p:sld xmlns:p="p" xmlns:a="a"><p:txBody><a:p><a:r><a:t>Ventas Q3</a:t></a:r></a:p></p:txBody></p:sld>"#,
+                ),
+                (
+                    "docProps/core.xml",
+                    r#"<cp:coreProperties xmlns:cp="cp" xmlns:dc="dc"><dc:title>Resultados</dc:title><dc:creator>Luis</dc:creator></cp:coreProperties>"#,
+                ),
+            ],
+        );
+
+        let doc = extract_office(&path, "pptx", 1024 * 1024).unwrap();
+        assert_eq!(doc.text, "Ventas Q3\n\nCierre\n\n");
+        assert_eq!(doc.title.as_deref(), Some("Resultados"));
+        assert_eq!(doc.author.as_deref(), Some("Luis"));
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn extracts_opendocument_text_and_meta() {
+        let path = std::env::temp_dir().join("aleph-office-test.odt");
+        write_zip(
+            &path,
+            &[
+                (
+                    "content.xml",
+                    r#"<office:document-content xmlns:office="o" xmlns:text="t"><office:body><office:text><text:h>Acta</text:h><text:p>Reunión del <text:span>lunes</text:span></text:p></office:text></office:body></office:document-content>"#,
+                ),
+                (
+                    "meta.xml",
+                    r#"<office:document-meta xmlns:office="o" xmlns:meta="m" xmlns:dc="dc"><office:meta><dc:title>Acta semanal</dc:title><meta:initial-creator>Marta</meta:initial-creator></office:meta></office:document-meta>"#,
+                ),
+                // Un core.xml de Office en un OpenDocument no cuenta
+                (
+                    "docProps/core.xml",
+                    r#"<cp:coreProperties xmlns:cp="cp" xmlns:dc="dc"><dc:title>Otro</dc:title></cp:coreProperties>"#,
+                ),
+            ],
+        );
+
+        let doc = extract_office(&path, "odt", 1024 * 1024).unwrap();
+        assert_eq!(doc.text, "Acta\nReunión del lunes\n");
+        assert_eq!(doc.title.as_deref(), Some("Acta semanal"));
+        assert_eq!(doc.author.as_deref(), Some("Marta"));
+        let _ = std::fs::remove_file(path);
+    }
+}
@@ -0,0 +1,247 @@
+use futures::future::BoxFuture;
+use futures::stream::{FuturesUnordered, StreamExt};
+use futures::FutureExt;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::time::Duration;
+
+use crate::apps;
+use crate::error::AlephError;
+use crate::files;
+use crate::ranking::Page;
+use crate::results::SearchResult;
+use crate::status;
+
+// Una fuente de resultados para el comando search (archivos, apps, ...)
+pub trait Provider: Send + Sync {
+    // Con este nombre se la pide desde el frontend y aparece en los avisos
+    fn name(&self) -> &'static str;
+
+    // Cuánto se la espera; si no termina a tiempo la búsqueda sigue sin ella
+    fn budget(&self) -> Duration;
+
+    // Los mejores `page.limit` resultados para la query, con su puntaje propio
+    fn search(&self, query: String, page: Page, explain: bool) -> BoxFuture<'static, Result<Vec<SearchResult>, AlephError>>;
+}
+
+pub struct AppsProvider;
+
+impl Provider for AppsProvider {
+    fn name(&self) -> &'static str {
+        "apps"
+    }
+
+    fn budget(&self) -> Duration {
+        Duration::from_millis(500)
+    }
+
+    fn search(&self, query: String, page: Page, explain: bool) -> BoxFuture<'static, Result<Vec<SearchResult>, AlephError>> {
+        async move { apps::rank_apps(&query, page, explain).await }.boxed()
+    }
+}
+
+pub struct FilesProvider;
+
+impl Provider for FilesProvider {
+    fn name(&self) -> &'static str {
+        "files"
+    }
+
+    // Puede tener que leer contenidos para los snippets: se le da más tiempo
+    fn budget(&self) -> Duration {
+        Duration::from_millis(1_500)
+    }
+
+    fn search(&self, query: String, page: Page, explain: bool) -> BoxFuture<'static, Result<Vec<SearchResult>, AlephError>> {
+        async move {
+            // Lee el índice y el disco: se hace fuera de los hilos del runtime
+            tokio::task::spawn_blocking(move || files::search_files(&query, None, page, explain))
+                .await
+                .map_err(|e| AlephError::Internal(e.to_string()))?
+        }
+        .boxed()
+    }
+}
+
+// Todas las fuentes, en el orden en que desempatan
+fn providers() -> Vec<Box<dyn Provider>> {
+    vec![Box::new(AppsProvider), Box::new(FilesProvider)]
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProviderFailure {
+    pub provider: &'static str,
+    pub error: AlephError,
+}
+
+// Lo que se sabe de una búsqueda hasta ahora: lo que devuelve search al final y lo
+// que viaja en los eventos `search-results` mientras faltan fuentes
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchUpdate {
+    // El id que mandó el frontend, para descartar avisos de búsquedas viejas
+    pub id: u64,
+    pub results: Vec<SearchResult>,
+    // Fuentes que todavía no respondieron
+    pub pending: Vec<&'static str>,
+    pub failed: Vec<ProviderFailure>,
+}
+
+// Cada fuente puntúa en su escala: se lleva todo a [0, 1] respecto de su mejor resultado
+fn normalize(results: &mut [SearchResult]) {
+    let max = results.iter().map(|r| r.score).fold(0.0, f32::max);
+    if max > 0.0 {
+        for result in results {
+            result.score /= max;
+        }
+    }
+}
+
+// Mezcla lo que respondió cada fuente (en el orden de providers()) por puntaje
+// normalizado; a igual puntaje va primero el de mejor posición en su fuente, así los
+// primeros de cada una quedan intercalados. Un path que viene de dos fuentes queda una vez
+fn interleave(batches: &[Option<Vec<SearchResult>>], limit: usize) -> Vec<SearchResult> {
+    let mut all: Vec<(usize, usize, &SearchResult)> = batches
+        .iter()
+        .enumerate()
+        .filter_map(|(source, batch)| Some((source, batch.as_ref()?)))
+        .flat_map(|(source, batch)| batch.iter().enumerate().map(move |(rank, r)| (rank, source, r)))
+        .collect();
+    all.sort_by(|a, b| {
+        b.2.score
+            .total_cmp(&a.2.score)
+            .then(a.0.cmp(&b.0))
+            .then(a.1.cmp(&b.1))
+    });
+
+    let mut seen = HashSet::new();
+    all.into_iter()
+        .filter(|(_, _, r)| seen.insert(r.path.as_str()))
+        .take(limit)
+        .map(|(_, _, r)| r.clone())
+        .collect()
+}
+
+// Busca en todas las fuentes (o en las de `sources`) a la vez. Cada una corre en su
+// propia tarea con su tiempo máximo; a medida que responden se avisa lo que hay con
+// el evento `search-results`, y al terminar se devuelve la mezcla final. Solo falla si
+// fallaron todas
+#[tauri::command]
+pub async fn search(
+    id: u64,
+    query: String,
+    sources: Option<Vec<String>>,
+    limit: Option<usize>,
+    explain: Option<bool>,
+) -> Result<SearchUpdate, AlephError> {
+    let page = Page::new(limit, None, None)?;
+    let mut update = SearchUpdate {
+        id,
+        results: Vec::new(),
+        pending: Vec::new(),
+        failed: Vec::new(),
+    };
+    if query.trim().is_empty() {
+        return Ok(update);
+    }
+
+    let mut selected = providers();
+    if let Some(sources) = &sources {
+        if let Some(unknown) = sources.iter().find(|s| selected.iter().all(|p| p.name() != s.as_str())) {
+            return Err(AlephError::InvalidInput(format!("no existe la fuente {:?}", unknown)));
+        }
+        selected.retain(|p| sources.iter().any(|s| s == p.name()));
+    }
+
+    let mut running: FuturesUnordered<_> = selected
+        .iter()
+        .enumerate()
+        .map(|(i, provider)| {
+            let (name, budget) = (provider.name(), provider.budget());
+            // Pasado el tiempo se deja de esperar, pero la tarea sigue (p. ej. el
+            // índice de apps que se llena la primera vez)
+            let task = tokio::spawn(provider.search(query.clone(), page, explain.unwrap_or(false)));
+            async move {
+                let result = match tokio::time::timeout(budget, task).await {
+                    Ok(Ok(result)) => result,
+                    Ok(Err(e)) => Err(AlephError::Internal(e.to_string())),
+                    Err(_) => Err(AlephError::Timeout(format!("{} ({} ms)", name, budget.as_millis()))),
+                };
+                (i, result)
+            }
+        })
+        .collect();
+
+    let mut batches: Vec<Option<Vec<SearchResult>>> = vec![None; selected.len()];
+    let mut done = vec![false; selected.len()];
+    while let Some((i, result)) = running.next().await {
+        done[i] = true;
+        match result {
+            Ok(mut results) => {
+                normalize(&mut results);
+                batches[i] = Some(results);
+            }
+            Err(error) => update.failed.push(ProviderFailure {
+                provider: selected[i].name(),
+                error,
+            }),
+        }
+        update.results = interleave(&batches, page.limit);
+        update.pending = selected
+            .iter()
+            .zip(&done)
+            .filter(|(_, done)| !**done)
+            .map(|(p, _)| p.name())
+            .collect();
+        if !update.pending.is_empty() {
+            status::emit("search-results", update.clone());
+        }
+    }
+
+    if !selected.is_empty() && update.failed.len() == selected.len() {
+        // Falló todo: se devuelve el error de la primera fuente
+        let first = selected[0].name();
+        if let Some(failure) = update.failed.iter().find(|f| f.provider == first) {
+            return Err(failure.error.clone());
+        }
+    }
+    Ok(update)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::results::ResultKind;
+
+    fn result(kind: ResultKind, path: &str, score: f32) -> SearchResult {
+        SearchResult::new(kind, path.to_string(), path.to_string(), score)
+    }
+
+    #[test]
+    fn interleaves_normalized_results() {
+        let mut apps = vec![
+            result(ResultKind::App, "/Applications/Notes.app", 3.0),
+            result(ResultKind::App, "/Applications/Numbers.app", 1.5),
+        ];
+        let mut files = vec![
+            result(ResultKind::File, "/docs/notes.md", 40.0),
+            result(ResultKind::File, "/docs/old-notes.md", 30.0),
+            result(ResultKind::App, "/Applications/Notes.app", 10.0),
+        ];
+        normalize(&mut apps);
+        normalize(&mut files);
+        assert_eq!(apps[1].score, 0.5);
+
+        let merged = interleave(&[Some(apps), Some(files)], 10);
+        let paths: Vec<&str> = merged.iter().map(|r| r.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                "/Applications/Notes.app",
+                "/docs/notes.md",
+                "/docs/old-notes.md",
+                "/Applications/Numbers.app"
+            ]
+        );
+        assert_eq!(interleave(&[None, Some(merged)], 2).len(), 2);
+    }
+}
@@ -0,0 +1,434 @@
+use chrono::{Local, NaiveDate, TimeZone};
+
+// Mini lenguaje de búsqueda de Aleph: texto libre, "frases exactas" y operadores
+//   ext:pdf  ext:jpg,png      extensión
+//   in:Downloads  in:~/src    carpeta (por nombre o path)
+//   kind:image  kind:folder   familia de tipos de archivo, o carpetas
+//   modified:<7d  modified:>2024-01-01  modified:today
+//   size:>10MB  size:<=500KB
+// Lo que no se entiende como operador queda como texto: nunca es un error
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ParsedQuery {
+    pub terms: Vec<String>,
+    pub phrases: Vec<String>,
+    pub exts: Vec<String>,
+    pub dirs: Vec<String>,
+    pub kinds: Vec<&'static str>,
+    // Rango de mtime (segundos unix, inclusivo)
+    pub modified_after: Option<i64>,
+    pub modified_before: Option<i64>,
+    // Rango de tamaño en bytes (inclusivo)
+    pub size_min: Option<u64>,
+    pub size_max: Option<u64>,
+}
+
+// Caracteres con significado para el QueryParser de tantivy; en el texto libre se
+// tratan como separadores
+const PARSER_SPECIAL: &[char] = &[
+    '+', '-', '^', '`', ':', '{', '}', '"', '[', ']', '(', ')', '~', '!', '\\', '*', '<', '>', '=',
+];
+
+impl ParsedQuery {
+    // El texto libre (palabras y frases) tal como lo escribió el usuario
+    pub fn text(&self) -> String {
+        let mut parts: Vec<String> = self.terms.clone();
+        parts.extend(self.phrases.iter().cloned());
+        parts.join(" ")
+    }
+
+    // El texto libre listo para el QueryParser de tantivy: palabras limpias y frases
+    // entre comillas
+    pub fn parser_input(&self) -> String {
+        let clean = |s: &str| s.replace(PARSER_SPECIAL, " ");
+        let mut parts: Vec<String> = self.terms.iter().map(|t| clean(t)).collect();
+        parts.extend(self.phrases.iter().map(|p| format!("\"{}\"", clean(p))));
+        parts.join(" ")
+    }
+
+    pub fn has_filters(&self) -> bool {
+        !self.exts.is_empty()
+            || !self.dirs.is_empty()
+            || !self.kinds.is_empty()
+            || self.modified_after.is_some()
+            || self.modified_before.is_some()
+            || self.size_min.is_some()
+            || self.size_max.is_some()
+    }
+
+    pub fn wants_folders(&self) -> bool {
+        self.kinds.contains(&FOLDER_KIND)
+    }
+
+    // Extensiones de todos los kind: pedidos
+    pub fn kind_extensions(&self) -> Vec<&'static str> {
+        self.kinds
+            .iter()
+            .filter_map(|k| KINDS.iter().find(|(name, _)| name == k))
+            .flat_map(|(_, exts)| exts.iter().copied())
+            .collect()
+    }
+}
+
+// kind:folder no es una familia de extensiones sino las carpetas indexadas
+pub const FOLDER_KIND: &str = "folder";
+
+// kind: -> extensiones
+const KINDS: &[(&str, &[&str])] = &[
+    (
+        "image",
+        &[
+            "jpg", "jpeg", "png", "gif", "bmp", "tiff", "tif", "webp", "heic", "heif", "svg",
+            "ico", "raw", "cr2", "nef", "psd",
+        ],
+    ),
+    (
+        "video",
+        &["mp4", "mov", "avi", "mkv", "wmv", "flv", "webm", "m4v", "mpg", "mpeg"],
+    ),
+    (
+        "audio",
+        &["mp3", "wav", "flac", "aac", "m4a", "ogg", "opus", "aiff", "wma"],
+    ),
+    (
+        "document",
+        &[
+            "pdf", "doc", "docx", "odt", "rtf", "txt", "md", "pages", "xls", "xlsx", "ods",
+            "csv", "ppt", "pptx", "odp", "key", "numbers", "epub", "tex",
+        ],
+    ),
+    (
+        "archive",
+        &["zip", "rar", "7z", "tar", "gz", "tgz", "bz2", "xz", "dmg", "iso"],
+    ),
+    (
+        "code",
+        &[
+            "rs", "c", "h", "cpp", "hpp", "cc", "m", "swift", "go", "py", "rb", "php", "java",
+            "kt", "scala", "js", "jsx", "ts", "tsx", "vue", "svelte", "html", "css", "scss",
+            "sh", "lua", "pl", "r", "dart", "ex", "hs", "ml", "cs", "zig", "sql",
+        ],
+    ),
+];
+
+// Sinónimos aceptados en kind:
+fn kind_name(value: &str) -> Option<&'static str> {
+    let value = value.to_lowercase();
+    let name = match value.as_str() {
+        "image" | "images" | "img" | "photo" | "picture" => "image",
+        "video" | "videos" | "movie" => "video",
+        "audio" | "music" | "sound" => "audio",
+        "document" | "documents" | "doc" | "docs" => "document",
+        "archive" | "archives" | "compressed" => "archive",
+        "code" | "source" => "code",
+        "folder" | "folders" | "dir" | "directory" => FOLDER_KIND,
+        _ => return None,
+    };
+    Some(name)
+}
+
+pub fn parse(input: &str) -> ParsedQuery {
+    parse_at(input, chrono::Utc::now().timestamp())
+}
+
+// `now` en segundos unix, para que modified: relativo sea testeable
+pub fn parse_at(input: &str, now: i64) -> ParsedQuery {
+    let mut parsed = ParsedQuery::default();
+
+    for token in tokenize(input) {
+        match token {
+            Token::Phrase(phrase) => parsed.phrases.push(phrase),
+            Token::Word(word) => {
+                if !apply_operator(&mut parsed, &word, now) {
+                    parsed.terms.push(word);
+                }
+            }
+        }
+    }
+    parsed
+}
+
+enum Token {
+    Word(String),
+    Phrase(String),
+}
+
+// Separa por espacios respetando comillas. Una comilla sin cerrar se toma como texto
+fn tokenize(input: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().peekable();
+    let mut word = String::new();
+
+    while let Some(c) = chars.next() {
+        if c.is_whitespace() {
+            if !word.is_empty() {
+                tokens.push(Token::Word(std::mem::take(&mut word)));
+            }
+        } else if c == '"' {
+            let rest: String = chars.clone().collect();
+            if let Some(end) = rest.find('"') {
+                let quoted = &rest[..end];
+                // consumir la frase y la comilla de cierre
+                chars.nth(quoted.chars().count());
+                if word.is_empty() {
+                    // "frase suelta"
+                    if !quoted.trim().is_empty() {
+                        tokens.push(Token::Phrase(quoted.trim().to_string()));
+                    }
+                } else {
+                    // operador:"valor con espacios"
+                    word.push_str(quoted);
+                }
+            }
+        } else {
+            word.push(c);
+        }
+    }
+    if !word.is_empty() {
+        tokens.push(Token::Word(word));
+    }
+    tokens
+}
+
+// true si `word` era un operador válido y quedó aplicado
+fn apply_operator(parsed: &mut ParsedQuery, word: &str, now: i64) -> bool {
+    let (key, value) = match word.split_once(':') {
+        Some((key, value)) if !value.is_empty() => (key.to_lowercase(), value),
+        _ => return false,
+    };
+
+    match key.as_str() {
+        "ext" => {
+            let exts: Vec<String> = value
+                .split(',')
+                .map(|e| e.trim().trim_start_matches('.').to_lowercase())
+                .filter(|e| !e.is_empty())
+                .collect();
+            if exts.is_empty() {
+                return false;
+            }
+            parsed.exts.extend(exts);
+        }
+        "in" => parsed.dirs.push(value.to_string()),
+        "kind" => {
+            let kinds: Option<Vec<&'static str>> = value.split(',').map(kind_name).collect();
+            match kinds {
+                Some(kinds) => parsed.kinds.extend(kinds),
+                None => return false,
+            }
+        }
+        "modified" => match parse_modified(value, now) {
+            Some((after, before)) => {
+                if after.is_some() {
+                    parsed.modified_after = after;
+                }
+                if before.is_some() {
+                    parsed.modified_before = before;
+                }
+            }
+            None => return false,
+        },
+        "size" => match parse_size_range(value) {
+            Some((min, max)) => {
+                if min.is_some() {
+                    parsed.size_min = min;
+                }
+                if max.is_some() {
+                    parsed.size_max = max;
+                }
+            }
+            None => return false,
+        },
+        _ => return false,
+    }
+    true
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Cmp {
+    Less,
+    LessEq,
+    Greater,
+    GreaterEq,
+    Eq,
+}
+
+fn split_cmp(value: &str) -> (Cmp, &str) {
+    for (prefix, cmp) in [
+        ("<=", Cmp::LessEq),
+        (">=", Cmp::GreaterEq),
+        ("<", Cmp::Less),
+        (">", Cmp::Greater),
+        ("=", Cmp::Eq),
+    ] {
+        if let Some(rest) = value.strip_prefix(prefix) {
+            return (cmp, rest);
+        }
+    }
+    (Cmp::Eq, value)
+}
+
+const DAY: i64 = 86_400;
+
+// modified:<7d = modificado en los últimos 7 días; modified:>7d = hace más de 7 días.
+// Con fecha absoluta los signos son de calendario: modified:>2024-01-01 = después de esa fecha
+fn parse_modified(value: &str, now: i64) -> Option<(Option<i64>, Option<i64>)> {
+    let (cmp, rest) = split_cmp(value);
+    let rest = rest.to_lowercase();
+
+    if let Some(day_start) = parse_day(&rest, now) {
+        let day_end = day_start + DAY - 1;
+        return Some(match cmp {
+            Cmp::Eq => (Some(day_start), Some(day_end)),
+            Cmp::Greater => (Some(day_end + 1), None),
+            Cmp::GreaterEq => (Some(day_start), None),
+            Cmp::Less => (None, Some(day_start - 1)),
+            Cmp::LessEq => (None, Some(day_end)),
+        });
+    }
+
+    let ago = now - parse_duration(&rest)?;
+    Some(match cmp {
+        // sin operador se entiende "en los últimos ..."
+        Cmp::Less | Cmp::LessEq | Cmp::Eq => (Some(ago), None),
+        Cmp::Greater | Cmp::GreaterEq => (None, Some(ago)),
+    })
+}
+
+// Inicio (medianoche local) de un día: today, yesterday o YYYY-MM-DD
+fn parse_day(value: &str, now: i64) -> Option<i64> {
+    let today = Local.timestamp_opt(now, 0).single()?.date_naive();
+    let date = match value {
+        "today" => today,
+        "yesterday" => today.pred_opt()?,
+        _ => NaiveDate::parse_from_str(value, "%Y-%m-%d").ok()?,
+    };
+    let midnight = date.and_hms_opt(0, 0, 0)?;
+    Some(Local.from_local_datetime(&midnight).earliest()?.timestamp())
+}
+
+// 30m, 12h, 7d, 2w, 3mo, 1y -> segundos
+fn parse_duration(value: &str) -> Option<i64> {
+    let split = value.find(|c: char| !c.is_ascii_digit())?;
+    let (number, unit) = value.split_at(split);
+    let number: i64 = number.parse().ok()?;
+    let unit_secs = match unit {
+        "m" | "min" => 60,
+        "h" => 3600,
+        "d" => DAY,
+        "w" => 7 * DAY,
+        "mo" => 30 * DAY,
+        "y" => 365 * DAY,
+        _ => return None,
+    };
+    number.checked_mul(unit_secs)
+}
+
+// size:>10MB, size:<=500k, size:1GB (sin operador = al menos)
+fn parse_size_range(value: &str) -> Option<(Option<u64>, Option<u64>)> {
+    let (cmp, rest) = split_cmp(value);
+    let bytes = parse_size(rest)?;
+    Some(match cmp {
+        Cmp::Greater => (Some(bytes.saturating_add(1)), None),
+        Cmp::GreaterEq | Cmp::Eq => (Some(bytes), None),
+        Cmp::Less => (None, Some(bytes.saturating_sub(1))),
+        Cmp::LessEq => (None, Some(bytes)),
+    })
+}
+
+fn parse_size(value: &str) -> Option<u64> {
+    let value = value.to_lowercase();
+    let split = value
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number: f64 = number.parse().ok()?;
+    let multiplier: u64 = match unit {
+        "" | "b" => 1,
+        "k" | "kb" => 1 << 10,
+        "m" | "mb" => 1 << 20,
+        "g" | "gb" => 1 << 30,
+        "t" | "tb" => 1 << 40,
+        _ => return None,
+    };
+    Some((number * multiplier as f64) as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: i64 = 1_700_000_000;
+
+    #[test]
+    fn parses_operators_and_text() {
+        let parsed = parse_at(
+            r#"factura ext:pdf,DOCX in:Downloads kind:image "informe anual" size:>10MB modified:<7d"#,
+            NOW,
+        );
+        assert_eq!(parsed.terms, vec!["factura"]);
+        assert_eq!(parsed.phrases, vec!["informe anual"]);
+        assert_eq!(parsed.exts, vec!["pdf", "docx"]);
+        assert_eq!(parsed.dirs, vec!["Downloads"]);
+        assert_eq!(parsed.kinds, vec!["image"]);
+        assert!(!parsed.wants_folders());
+        assert_eq!(parsed.size_min, Some(10 * 1024 * 1024 + 1));
+        assert_eq!(parsed.modified_after, Some(NOW - 7 * DAY));
+        assert_eq!(parsed.modified_before, None);
+        assert_eq!(parsed.text(), "factura informe anual");
+    }
+
+    #[test]
+    fn quoted_operator_values() {
+        let parsed = parse_at(r#"in:"My Files" notas"#, NOW);
+        assert_eq!(parsed.dirs, vec!["My Files"]);
+        assert_eq!(parsed.terms, vec!["notas"]);
+    }
+
+    #[test]
+    fn folder_kind_has_no_extensions() {
+        let parsed = parse_at("kind:dir,image", NOW);
+        assert!(parsed.wants_folders());
+        assert!(parsed.kind_extensions().contains(&"png"));
+        assert_eq!(parse_at("kind:folder", NOW).kind_extensions(), Vec::<&str>::new());
+    }
+
+    #[test]
+    fn bad_syntax_falls_back_to_text() {
+        let parsed = parse_at(r#"size:>lots kind:spaceship foo: http://x "abierta"#, NOW);
+        assert!(!parsed.has_filters());
+        assert_eq!(
+            parsed.terms,
+            vec!["size:>lots", "kind:spaceship", "foo:", "http://x", "abierta"]
+        );
+    }
+
+    #[test]
+    fn parser_input_drops_query_syntax() {
+        assert_eq!(parse_at(r#"a:b "x(y)" ext:pdf"#, NOW).parser_input(), r#"a b "x y ""#);
+        assert_eq!(parse_at("\"", NOW).parser_input(), "");
+    }
+
+    #[test]
+    fn modified_ranges() {
+        let older = parse_at("modified:>2w", NOW);
+        assert_eq!(older.modified_before, Some(NOW - 14 * DAY));
+        assert_eq!(older.modified_after, None);
+
+        let after = parse_at("modified:>=2024-01-01", NOW);
+        assert!(after.modified_after.is_some());
+        let day = parse_at("modified:2024-01-01", NOW);
+        assert_eq!(
+            day.modified_before.unwrap() - day.modified_after.unwrap(),
+            DAY - 1
+        );
+    }
+
+    #[test]
+    fn size_units() {
+        assert_eq!(parse_size("500kb"), Some(500 * 1024));
+        assert_eq!(parse_size("1.5G"), Some(1536 * 1024 * 1024));
+        assert_eq!(parse_size("12"), Some(12));
+        assert_eq!(parse_size("10 parsecs"), None);
+        assert_eq!(parse_at("size:<=1MB", NOW).size_max, Some(1 << 20));
+    }
+}
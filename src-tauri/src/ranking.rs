@@ -0,0 +1,98 @@
+use serde::{Deserialize, Serialize};
+
+use crate::error::AlephError;
+
+// Resultados por página si el frontend no pide otra cantidad, y el máximo que se acepta
+pub const DEFAULT_LIMIT: usize = 15;
+pub const MAX_LIMIT: usize = 200;
+// Hasta dónde se puede paginar; un offset mayor es un error
+pub const MAX_OFFSET: usize = 1_000;
+
+// Orden de los resultados. Salvo por relevancia, el orden es fijo: lo más nuevo, lo
+// más grande, y por nombre de la A a la Z
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortMode {
+    #[default]
+    Relevance,
+    Modified,
+    Size,
+    Name,
+}
+
+// Qué parte de los resultados se pide
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Page {
+    pub limit: usize,
+    pub offset: usize,
+    pub sort: SortMode,
+}
+
+impl Page {
+    pub fn new(limit: Option<usize>, offset: Option<usize>, sort: Option<SortMode>) -> Result<Self, AlephError> {
+        let offset = offset.unwrap_or(0);
+        if offset > MAX_OFFSET {
+            return Err(AlephError::BadQuery(format!("offset {} mayor que {}", offset, MAX_OFFSET)));
+        }
+        Ok(Page {
+            limit: limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT),
+            offset,
+            sort: sort.unwrap_or_default(),
+        })
+    }
+
+    pub fn slice<T>(&self, items: Vec<T>) -> Vec<T> {
+        items.into_iter().skip(self.offset).take(self.limit).collect()
+    }
+}
+
+// Desglose del puntaje de un resultado: lo que dio tantivy y cada multiplicador que se
+// le aplicó después, en orden. Sirve para entender por qué algo quedó donde quedó
+#[derive(Debug, Clone, Serialize)]
+pub struct Explanation {
+    pub base: f32,
+    pub factors: Vec<Factor>,
+    pub score: f32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Factor {
+    pub name: &'static str,
+    pub multiplier: f32,
+}
+
+impl Explanation {
+    pub fn new(base: f32) -> Self {
+        Explanation {
+            base,
+            factors: Vec::new(),
+            score: base,
+        }
+    }
+
+    // Se anotan todos los factores, también los que quedan en 1.0
+    pub fn apply(&mut self, name: &'static str, multiplier: f32) {
+        self.factors.push(Factor { name, multiplier });
+        self.score *= multiplier;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pages_clamp_limits_and_slice() {
+        let page = Page::new(None, None, None).unwrap();
+        assert_eq!((page.limit, page.offset, page.sort), (DEFAULT_LIMIT, 0, SortMode::Relevance));
+        assert_eq!(Page::new(Some(0), None, None).unwrap().limit, 1);
+        assert_eq!(Page::new(Some(10_000), None, None).unwrap().limit, MAX_LIMIT);
+        assert_eq!(Page::new(Some(usize::MAX), Some(MAX_OFFSET), None).unwrap().limit, MAX_LIMIT);
+        assert!(matches!(Page::new(None, Some(usize::MAX), None), Err(AlephError::BadQuery(_))));
+
+        let page = Page::new(Some(2), Some(3), Some(SortMode::Name)).unwrap();
+        assert_eq!(page.slice((0..10).collect()), vec![3, 4]);
+        assert!(page.slice((0..3).collect::<Vec<_>>()).is_empty());
+        assert_eq!(serde_json::from_str::<SortMode>("\"modified\"").unwrap(), SortMode::Modified);
+    }
+}
@@ -0,0 +1,197 @@
+use notify::event::RenameMode;
+use notify::Event;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+// Cuánto se espera la otra mitad de un rename antes de darlo por borrado
+pub const PAIR_WINDOW: Duration = Duration::from_millis(500);
+
+// Qué hacer en el índice después de un evento de rename
+#[derive(Debug, Clone, PartialEq)]
+pub enum RenameAction {
+    Moved { from: PathBuf, to: PathBuf },
+    // Se fue fuera de lo que miramos (o a la papelera)
+    Removed(PathBuf),
+    // Llegó desde fuera de lo que miramos
+    Added(PathBuf),
+}
+
+// Junta las dos mitades de un rename. Según la plataforma llegan como un solo evento
+// (RenameMode::Both, con los dos paths), como From/To con el mismo tracker (inotify)
+// o como dos eventos Any sueltos (FSEvents), donde el lado que ya no existe es el origen.
+// inotify manda además un Both después de From/To con el mismo tracker: ese rename ya
+// se hizo y se ignora
+#[derive(Debug, Default)]
+pub struct RenameTracker {
+    pending: Option<(PathBuf, Option<usize>, Instant)>,
+    // Tracker del último From/To que se emparejó
+    paired: Option<usize>,
+}
+
+impl RenameTracker {
+    pub fn push(&mut self, mode: RenameMode, event: &Event) -> Vec<RenameAction> {
+        let mut actions = Vec::new();
+        match mode {
+            RenameMode::Both if event.tracker().is_some() && event.tracker() == self.paired => {
+                self.paired = None;
+            }
+            RenameMode::Both if event.paths.len() >= 2 => {
+                actions.extend(self.flush());
+                actions.push(RenameAction::Moved {
+                    from: event.paths[0].clone(),
+                    to: event.paths[1].clone(),
+                });
+            }
+            RenameMode::From => {
+                for path in &event.paths {
+                    actions.extend(self.flush());
+                    self.pending = Some((path.clone(), event.tracker(), Instant::now()));
+                }
+            }
+            RenameMode::To => {
+                for path in &event.paths {
+                    actions.extend(self.pair(path.clone(), event.tracker()));
+                }
+            }
+            _ => {
+                for path in &event.paths {
+                    if path.exists() {
+                        actions.extend(self.pair(path.clone(), None));
+                    } else {
+                        actions.extend(self.flush());
+                        self.pending = Some((path.clone(), None, Instant::now()));
+                    }
+                }
+            }
+        }
+        actions
+    }
+
+    // El destino de un rename: se empareja con el origen pendiente si corresponde;
+    // si no, el origen pendiente queda como borrado y el destino como nuevo
+    fn pair(&mut self, to: PathBuf, tracker: Option<usize>) -> Vec<RenameAction> {
+        match self.pending.take() {
+            Some((from, pending_tracker, at))
+                if at.elapsed() <= PAIR_WINDOW
+                    && (tracker.is_none()
+                        || pending_tracker.is_none()
+                        || tracker == pending_tracker) =>
+            {
+                self.paired = tracker;
+                vec![RenameAction::Moved { from, to }]
+            }
+            Some((from, _, _)) => vec![RenameAction::Removed(from), RenameAction::Added(to)],
+            None => vec![RenameAction::Added(to)],
+        }
+    }
+
+    pub fn has_pending(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    // Un origen que no encontró pareja se da por borrado
+    pub fn flush(&mut self) -> Option<RenameAction> {
+        self.pending
+            .take()
+            .map(|(from, _, _)| RenameAction::Removed(from))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use notify::event::{EventKind, ModifyKind};
+
+    fn rename(mode: RenameMode, paths: &[&str], tracker: Option<usize>) -> Event {
+        let mut event = Event::new(EventKind::Modify(ModifyKind::Name(mode)));
+        for p in paths {
+            event = event.add_path(PathBuf::from(p));
+        }
+        match tracker {
+            Some(t) => event.set_tracker(t),
+            None => event,
+        }
+    }
+
+    #[test]
+    fn pairs_both_sides_of_a_rename() {
+        let mut tracker = RenameTracker::default();
+        let moved = RenameAction::Moved {
+            from: PathBuf::from("/a/old.txt"),
+            to: PathBuf::from("/a/new.txt"),
+        };
+
+        let both = rename(RenameMode::Both, &["/a/old.txt", "/a/new.txt"], None);
+        assert_eq!(tracker.push(RenameMode::Both, &both), vec![moved.clone()]);
+
+        let from = rename(RenameMode::From, &["/a/old.txt"], Some(7));
+        let to = rename(RenameMode::To, &["/a/new.txt"], Some(7));
+        assert!(tracker.push(RenameMode::From, &from).is_empty());
+        assert!(tracker.has_pending());
+        assert_eq!(tracker.push(RenameMode::To, &to), vec![moved]);
+        assert!(!tracker.has_pending());
+    }
+
+    #[test]
+    fn inotify_both_after_from_to_is_not_a_second_move() {
+        let mut tracker = RenameTracker::default();
+        let moved = RenameAction::Moved {
+            from: PathBuf::from("/a/dir"),
+            to: PathBuf::from("/a/renamed"),
+        };
+
+        // Lo que manda inotify para un solo rename
+        let from = rename(RenameMode::From, &["/a/dir"], Some(9));
+        let to = rename(RenameMode::To, &["/a/renamed"], Some(9));
+        let both = rename(RenameMode::Both, &["/a/dir", "/a/renamed"], Some(9));
+        assert!(tracker.push(RenameMode::From, &from).is_empty());
+        assert_eq!(tracker.push(RenameMode::To, &to), vec![moved.clone()]);
+        assert!(tracker.push(RenameMode::Both, &both).is_empty());
+
+        // Un Both con otro tracker sí es un rename nuevo
+        let other = rename(RenameMode::Both, &["/a/dir", "/a/renamed"], Some(10));
+        assert_eq!(tracker.push(RenameMode::Both, &other), vec![moved]);
+    }
+
+    #[test]
+    fn unpaired_sides_become_remove_and_add() {
+        let mut tracker = RenameTracker::default();
+
+        let from = rename(RenameMode::From, &["/a/old.txt"], Some(1));
+        let to = rename(RenameMode::To, &["/b/other.txt"], Some(2));
+        tracker.push(RenameMode::From, &from);
+        assert_eq!(
+            tracker.push(RenameMode::To, &to),
+            vec![
+                RenameAction::Removed(PathBuf::from("/a/old.txt")),
+                RenameAction::Added(PathBuf::from("/b/other.txt")),
+            ]
+        );
+
+        tracker.push(RenameMode::From, &from);
+        assert_eq!(
+            tracker.flush(),
+            Some(RenameAction::Removed(PathBuf::from("/a/old.txt")))
+        );
+    }
+
+    #[test]
+    fn any_mode_uses_existence_to_pick_the_side() {
+        let dir = std::env::temp_dir().join("aleph-renames-test");
+        std::fs::create_dir_all(&dir).unwrap();
+        let new = dir.join("new.txt");
+        std::fs::write(&new, "x").unwrap();
+        let old = dir.join("old.txt");
+        let _ = std::fs::remove_file(&old);
+
+        let mut tracker = RenameTracker::default();
+        let gone = rename(RenameMode::Any, &[old.to_str().unwrap()], None);
+        let arrived = rename(RenameMode::Any, &[new.to_str().unwrap()], None);
+        assert!(tracker.push(RenameMode::Any, &gone).is_empty());
+        assert_eq!(
+            tracker.push(RenameMode::Any, &arrived),
+            vec![RenameAction::Moved { from: old, to: new }]
+        );
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,75 @@
+use serde::Serialize;
+
+use crate::ranking::Explanation;
+
+// Qué es un resultado
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ResultKind {
+    File,
+    Dir,
+    App,
+}
+
+// De dónde salió el match de un resultado
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MatchSource {
+    Name,
+    Content,
+}
+
+// Lo que se puede hacer con un resultado desde la UI
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    // open_path
+    Open,
+    // reveal_path: abre la carpeta que lo contiene
+    Reveal,
+    // Lo resuelve el frontend
+    CopyPath,
+}
+
+// Un resultado de cualquier búsqueda (archivos o apps), tal como lo recibe el frontend
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchResult {
+    pub kind: ResultKind,
+    // Lo que se muestra en grande (el nombre) y abajo (el path)
+    pub title: String,
+    pub subtitle: String,
+    pub path: String,
+    pub score: f32,
+    // Data URL del ícono; sin ícono el frontend usa uno según el tipo
+    pub icon: Option<String>,
+    pub matched: MatchSource,
+    // Fragmento del contenido alrededor del match (solo si matched == Content)
+    pub snippet: Option<String>,
+    // Caracteres [inicio, fin) del título y del subtítulo que matchean la query
+    pub title_ranges: Vec<(usize, usize)>,
+    pub subtitle_ranges: Vec<(usize, usize)>,
+    pub actions: Vec<Action>,
+    // Desglose del puntaje (solo con explain_search / explain_app_search)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub explain: Option<Explanation>,
+}
+
+impl SearchResult {
+    // Un resultado con el path de subtítulo y las acciones de siempre
+    pub fn new(kind: ResultKind, title: String, path: String, score: f32) -> Self {
+        SearchResult {
+            kind,
+            title,
+            subtitle: path.clone(),
+            path,
+            score,
+            icon: None,
+            matched: MatchSource::Name,
+            snippet: None,
+            title_ranges: Vec::new(),
+            subtitle_ranges: Vec::new(),
+            actions: vec![Action::Open, Action::Reveal, Action::CopyPath],
+            explain: None,
+        }
+    }
+}
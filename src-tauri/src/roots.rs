@@ -0,0 +1,305 @@
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+use crate::error::AlephError;
+use crate::files;
+
+// Una raíz indexada: carpeta + reglas propias de qué entra al índice
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct IndexRoot {
+    pub path: PathBuf,
+    #[serde(default)]
+    pub include: Vec<String>,
+    #[serde(default)]
+    pub exclude: Vec<String>,
+    #[serde(default)]
+    pub max_depth: Option<usize>,
+    #[serde(default = "default_follow_symlinks")]
+    pub follow_symlinks: bool,
+}
+
+fn default_follow_symlinks() -> bool {
+    true
+}
+
+impl IndexRoot {
+    pub fn new(path: PathBuf) -> Self {
+        IndexRoot {
+            path,
+            include: Vec::new(),
+            exclude: Vec::new(),
+            max_depth: None,
+            follow_symlinks: default_follow_symlinks(),
+        }
+    }
+
+    pub fn filter(&self) -> Result<RootFilter, String> {
+        RootFilter::new(self)
+    }
+}
+
+// Reglas compiladas de una raíz (los globs se matchean contra el path relativo a la raíz)
+#[derive(Debug, Clone)]
+pub struct RootFilter {
+    pub root: IndexRoot,
+    include: Option<GlobSet>,
+    exclude: GlobSet,
+}
+
+impl RootFilter {
+    fn new(root: &IndexRoot) -> Result<Self, String> {
+        let include = if root.include.is_empty() {
+            None
+        } else {
+            Some(build_globset(&root.include)?)
+        };
+        let exclude = build_globset(&root.exclude)?;
+        Ok(RootFilter {
+            root: root.clone(),
+            include,
+            exclude,
+        })
+    }
+
+    // true si el path (archivo o carpeta) queda fuera de la raíz: fuera del árbol,
+    // más profundo que max_depth, o él o algún ancestro matchea un exclude
+    pub fn is_excluded(&self, path: &Path) -> bool {
+        let rel = match path.strip_prefix(&self.root.path) {
+            Ok(rel) => rel,
+            Err(_) => return true,
+        };
+        if let Some(max_depth) = self.root.max_depth {
+            if rel.components().count() > max_depth {
+                return true;
+            }
+        }
+        rel.ancestors()
+            .filter(|a| !a.as_os_str().is_empty())
+            .any(|a| {
+                self.exclude.is_match(a)
+                    || a.file_name().map(|n| self.exclude.is_match(n)).unwrap_or(false)
+            })
+    }
+
+    // Un archivo entra al índice si no está excluido y matchea algún include (si hay)
+    pub fn accepts_file(&self, path: &Path) -> bool {
+        if self.is_excluded(path) {
+            return false;
+        }
+        match &self.include {
+            None => true,
+            Some(set) => {
+                let rel = path.strip_prefix(&self.root.path).unwrap_or(path);
+                set.is_match(rel) || path.file_name().map(|n| set.is_match(n)).unwrap_or(false)
+            }
+        }
+    }
+}
+
+fn build_globset(patterns: &[String]) -> Result<GlobSet, String> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let glob = Glob::new(pattern).map_err(|e| format!("glob inválido {:?}: {}", pattern, e))?;
+        builder.add(glob);
+    }
+    builder.build().map_err(|e| e.to_string())
+}
+
+// Las carpetas que se indexaban antes de que las raíces fueran configurables
+fn default_roots() -> Vec<IndexRoot> {
+    let mut roots = Vec::new();
+    if let Some(desktop_dir) = dirs::desktop_dir() {
+        roots.push(IndexRoot::new(desktop_dir));
+    }
+    if let Some(home) = dirs::home_dir() {
+        for name in [
+            "Documents",
+            "Downloads",
+            "Pictures",
+            "Music",
+            "Movies",
+            "Library",
+            "Public",
+        ] {
+            roots.push(IndexRoot::new(home.join(name)));
+        }
+    }
+    roots
+}
+
+fn config_path() -> Result<PathBuf, String> {
+    let home = dirs::home_dir().ok_or_else(|| "no home dir".to_string())?;
+    Ok(home.join(".config/aleph/roots.json"))
+}
+
+fn load_roots() -> Vec<IndexRoot> {
+    let path = match config_path() {
+        Ok(p) => p,
+        Err(_) => return default_roots(),
+    };
+    match fs::read_to_string(&path) {
+        Ok(raw) => match serde_json::from_str::<Vec<IndexRoot>>(&raw) {
+            Ok(roots) => roots,
+            Err(e) => {
+                println!("roots.json inválido ({}), usando las raíces por defecto", e);
+                default_roots()
+            }
+        },
+        Err(_) => default_roots(),
+    }
+}
+
+fn save_roots(filters: &[RootFilter]) -> Result<(), String> {
+    let path = config_path()?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let roots: Vec<&IndexRoot> = filters.iter().map(|f| &f.root).collect();
+    let raw = serde_json::to_string_pretty(&roots).map_err(|e| e.to_string())?;
+    fs::write(path, raw).map_err(|e| e.to_string())
+}
+
+// Lista de raíces en memoria, cargada de disco la primera vez que se usa
+fn registry() -> &'static Mutex<Vec<RootFilter>> {
+    static REGISTRY: OnceLock<Mutex<Vec<RootFilter>>> = OnceLock::new();
+    REGISTRY.get_or_init(|| {
+        let filters = load_roots()
+            .iter()
+            .filter_map(|root| match root.filter() {
+                Ok(f) => Some(f),
+                Err(e) => {
+                    println!("Ignorando raíz {:?}: {}", root.path, e);
+                    None
+                }
+            })
+            .collect();
+        Mutex::new(filters)
+    })
+}
+
+pub fn roots() -> Vec<IndexRoot> {
+    registry()
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|f| f.root.clone())
+        .collect()
+}
+
+// La raíz más específica que contiene al path (las raíces pueden anidarse)
+pub fn filter_for_path(path: &Path) -> Option<RootFilter> {
+    registry()
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|f| path.starts_with(&f.root.path))
+        .max_by_key(|f| f.root.path.components().count())
+        .cloned()
+}
+
+pub fn expand_home(path: &str) -> Result<PathBuf, AlephError> {
+    if path == "~" || path.starts_with("~/") {
+        let home = dirs::home_dir().ok_or(AlephError::NoHomeDir)?;
+        Ok(home.join(path.trim_start_matches('~').trim_start_matches('/')))
+    } else {
+        Ok(PathBuf::from(path))
+    }
+}
+
+// La raíz configurada en `path` (acepta ~)
+pub fn find_root(path: &str) -> Result<IndexRoot, AlephError> {
+    let path = expand_home(path)?;
+    roots()
+        .into_iter()
+        .find(|r| r.path == path)
+        .ok_or_else(|| AlephError::NotFound(format!("no existe la raíz {:?}", path)))
+}
+
+#[tauri::command]
+pub fn list_roots() -> Vec<IndexRoot> {
+    roots()
+}
+
+#[tauri::command]
+pub async fn add_root(mut root: IndexRoot) -> Result<Vec<IndexRoot>, AlephError> {
+    root.path = expand_home(&root.path.to_string_lossy())?;
+    if !root.path.is_absolute() {
+        return Err(AlephError::InvalidInput(format!("el path debe ser absoluto: {:?}", root.path)));
+    }
+    if !root.path.is_dir() {
+        return Err(AlephError::InvalidInput(format!("no es un directorio: {:?}", root.path)));
+    }
+    // Un glob de include/exclude que no compila
+    let filter = root.filter().map_err(AlephError::InvalidInput)?;
+
+    let replaced = {
+        let mut filters = registry().lock().unwrap();
+        let replaced = match filters.iter().position(|f| f.root.path == root.path) {
+            Some(i) => {
+                filters[i] = filter;
+                true
+            }
+            None => {
+                filters.push(filter);
+                false
+            }
+        };
+        save_roots(&filters)?;
+        replaced
+    };
+
+    // Si cambiaron las reglas de una raíz existente, su índice viejo ya no sirve
+    if replaced {
+        files::drop_root_index(&root).await?;
+    }
+    files::spawn_root(root);
+
+    Ok(roots())
+}
+
+#[tauri::command]
+pub async fn remove_root(path: String) -> Result<Vec<IndexRoot>, AlephError> {
+    let path = expand_home(&path)?;
+    let removed = {
+        let mut filters = registry().lock().unwrap();
+        let removed = match filters.iter().position(|f| f.root.path == path) {
+            Some(i) => filters.remove(i).root,
+            None => return Err(AlephError::NotFound(format!("no existe la raíz {:?}", path))),
+        };
+        save_roots(&filters)?;
+        removed
+    };
+
+    files::drop_root_index(&removed).await?;
+
+    Ok(roots())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn root_filter_rules() {
+        let root = IndexRoot {
+            path: PathBuf::from("/mnt/data"),
+            include: vec!["*.csv".to_string(), "*.md".to_string()],
+            exclude: vec!["node_modules".to_string(), "tmp/**".to_string()],
+            max_depth: Some(3),
+            follow_symlinks: false,
+        };
+        let filter = root.filter().unwrap();
+
+        assert!(filter.accepts_file(Path::new("/mnt/data/a/b.csv")));
+        assert!(!filter.accepts_file(Path::new("/mnt/data/a/b.txt")));
+        assert!(!filter.accepts_file(Path::new("/mnt/data/x/node_modules/y.md")));
+        assert!(!filter.accepts_file(Path::new("/mnt/data/tmp/notes.md")));
+        assert!(!filter.accepts_file(Path::new("/mnt/data/a/b/c/d.csv")));
+        assert!(!filter.accepts_file(Path::new("/home/other/a.csv")));
+        assert!(filter.is_excluded(Path::new("/mnt/data/x/node_modules")));
+        assert!(!filter.is_excluded(Path::new("/mnt/data/x")));
+    }
+}
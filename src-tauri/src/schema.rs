@@ -0,0 +1,288 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tantivy::schema::*;
+use tantivy::{Index, TantivyError};
+
+use crate::tokenizer;
+
+// Archivo con la versión del schema, al lado del meta.json de tantivy
+const VERSION_FILE: &str = "aleph_schema.json";
+
+// El schema de un índice con su versión. Hay que subir `version` ante cualquier cambio
+// que deje viejos los documentos ya indexados, aunque el Schema de tantivy no cambie
+// (p. ej. un tokenizer que ahora corta distinto)
+pub struct SchemaDef {
+    pub name: &'static str,
+    pub version: u32,
+    build: fn() -> Schema,
+}
+
+impl SchemaDef {
+    pub fn schema(&self) -> Schema {
+        (self.build)()
+    }
+}
+
+pub const FILES: SchemaDef = SchemaDef {
+    name: "files",
+    version: 5,
+    build: files_schema,
+};
+
+pub const APPS: SchemaDef = SchemaDef {
+    name: "apps",
+    version: 4,
+    build: apps_schema,
+};
+
+fn files_schema() -> Schema {
+    let mut schema_builder = Schema::builder();
+
+    schema_builder.add_text_field("path", STORED | STRING);
+    // El nombre se corta por partes de identificador; el subfield guarda sus prefijos
+    schema_builder.add_text_field("filename", tokenizer::ident_options().set_stored());
+    schema_builder.add_text_field("filename_ngram", tokenizer::prefix_options());
+    // El nombre plegado, para ordenar por nombre sin leer los documentos
+    schema_builder.add_text_field("name_sort", STRING | FAST);
+    schema_builder.add_text_field("extension", STRING | STORED);
+    schema_builder.add_text_field("root", STRING | STORED);
+    // Cada carpeta de la raíz que contiene al archivo, para borrar o filtrar un
+    // subárbol entero con un solo término
+    schema_builder.add_text_field("ancestors", STRING);
+    // "file" o "dir"
+    schema_builder.add_text_field("kind", STRING | STORED);
+    // Texto de los archivos de texto/código; se guarda (en NFC) para poder armar snippets
+    schema_builder.add_text_field("content", tokenizer::text_options().set_stored());
+    // Metadatos de documentos (Office/OpenDocument)
+    schema_builder.add_text_field("title", tokenizer::text_options().set_stored());
+    schema_builder.add_text_field("author", tokenizer::text_options().set_stored());
+    // Fecha de modificación (segundos unix) y tamaño en bytes, para los filtros modified:/size:
+    schema_builder.add_i64_field("mtime", INDEXED | FAST | STORED);
+    schema_builder.add_u64_field("size", INDEXED | FAST | STORED);
+
+    schema_builder.build()
+}
+
+fn apps_schema() -> Schema {
+    let mut schema_builder = Schema::builder();
+
+    schema_builder.add_text_field("path", STORED | STRING);
+    schema_builder.add_text_field("filename", tokenizer::ident_options().set_stored());
+    schema_builder.add_text_field("filename_ngram", tokenizer::prefix_options());
+    schema_builder.add_text_field("extension", STRING | STORED);
+
+    schema_builder.build()
+}
+
+#[derive(Serialize, Deserialize)]
+struct VersionInfo {
+    name: String,
+    version: u32,
+}
+
+fn stored_version(dir: &Path) -> Option<u32> {
+    let raw = fs::read_to_string(dir.join(VERSION_FILE)).ok()?;
+    serde_json::from_str::<VersionInfo>(&raw).ok().map(|v| v.version)
+}
+
+fn write_version(def: &SchemaDef, dir: &Path) -> Result<(), String> {
+    let info = VersionInfo {
+        name: def.name.to_string(),
+        version: def.version,
+    };
+    let raw = serde_json::to_string(&info).map_err(|e| e.to_string())?;
+    fs::write(dir.join(VERSION_FILE), raw).map_err(|e| e.to_string())
+}
+
+// Donde se arma el índice nuevo durante una migración
+fn next_dir(dir: &Path) -> PathBuf {
+    let name = dir
+        .file_name()
+        .map(|n| n.to_string_lossy())
+        .unwrap_or_default();
+    dir.with_file_name(format!("{}.next", name))
+}
+
+pub enum Opened {
+    // El índice se creó vacío: hay que llenarlo
+    Created(Index),
+    // El índice en disco ya está en la versión actual
+    Current(Index),
+    // El índice en disco es de otra versión: se sigue leyendo `old` mientras se arma
+    // `fresh` en `fresh_dir`, y al terminar se llama a `finish`
+    Migrate {
+        old: Index,
+        fresh: Index,
+        fresh_dir: PathBuf,
+    },
+}
+
+// Abre el índice de `dir` con el schema de `def` y le registra los tokenizers propios.
+// `readable` dice si un índice de otra versión todavía sirve para buscar mientras se
+// migra; si no, se rehace en el lugar
+pub fn open(def: &SchemaDef, dir: &Path, readable: impl Fn(&Schema) -> bool) -> Result<Opened, String> {
+    let opened = open_unregistered(def, dir, readable)?;
+    match &opened {
+        Opened::Created(index) | Opened::Current(index) => tokenizer::register(index),
+        Opened::Migrate { old, fresh, .. } => {
+            tokenizer::register(old);
+            tokenizer::register(fresh);
+        }
+    }
+    Ok(opened)
+}
+
+fn open_unregistered(
+    def: &SchemaDef,
+    dir: &Path,
+    readable: impl Fn(&Schema) -> bool,
+) -> Result<Opened, String> {
+    let next = next_dir(dir);
+    promote(def, dir, &next)?;
+    fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+
+    let index = match Index::create_in_dir(dir, def.schema()) {
+        Ok(index) => {
+            write_version(def, dir)?;
+            return Ok(Opened::Created(index));
+        }
+        Err(TantivyError::IndexAlreadyExists) => {
+            Index::open_in_dir(dir).map_err(|e| e.to_string())?
+        }
+        Err(e) => return Err(e.to_string()),
+    };
+
+    let same_schema = index.schema() == def.schema();
+    match stored_version(dir) {
+        Some(version) if version == def.version && same_schema => {
+            return Ok(Opened::Current(index));
+        }
+        // Índices de antes del versionado: si el schema coincide se adoptan tal cual
+        None if same_schema => {
+            write_version(def, dir)?;
+            return Ok(Opened::Current(index));
+        }
+        _ => {}
+    }
+
+    if !readable(&index.schema()) {
+        // No hay nada que se pueda leer mientras tanto: se rehace directamente
+        drop(index);
+        fs::remove_dir_all(dir).map_err(|e| e.to_string())?;
+        fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        let index = Index::create_in_dir(dir, def.schema()).map_err(|e| e.to_string())?;
+        write_version(def, dir)?;
+        return Ok(Opened::Created(index));
+    }
+
+    println!(
+        "Index {} is at version {:?}, migrating to {}",
+        def.name,
+        stored_version(dir),
+        def.version
+    );
+    if next.exists() {
+        fs::remove_dir_all(&next).map_err(|e| e.to_string())?;
+    }
+    fs::create_dir_all(&next).map_err(|e| e.to_string())?;
+    let fresh = Index::create_in_dir(&next, def.schema()).map_err(|e| e.to_string())?;
+    Ok(Opened::Migrate {
+        old: index,
+        fresh,
+        fresh_dir: next,
+    })
+}
+
+// La migración terminó: el índice nuevo reemplaza al de `dir` en el próximo arranque
+// (mientras tanto se sigue usando desde `fresh_dir`)
+pub fn finish(def: &SchemaDef, fresh_dir: &Path) -> Result<(), String> {
+    write_version(def, fresh_dir)
+}
+
+fn promote(def: &SchemaDef, dir: &Path, next: &Path) -> Result<(), String> {
+    if !next.exists() {
+        return Ok(());
+    }
+    if stored_version(next) == Some(def.version) {
+        if dir.exists() {
+            fs::remove_dir_all(dir).map_err(|e| e.to_string())?;
+        }
+        fs::rename(next, dir).map_err(|e| e.to_string())
+    } else {
+        // Una migración que quedó por la mitad: se vuelve a empezar
+        fs::remove_dir_all(next).map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tantivy::collector::Count;
+    use tantivy::query::AllQuery;
+    use tantivy::doc;
+
+    fn v1_schema() -> Schema {
+        let mut builder = Schema::builder();
+        builder.add_text_field("path", STRING | STORED);
+        builder.build()
+    }
+
+    fn v2_schema() -> Schema {
+        let mut builder = Schema::builder();
+        builder.add_text_field("path", STRING | STORED);
+        builder.add_text_field("name", TEXT | STORED);
+        builder.build()
+    }
+
+    const V1: SchemaDef = SchemaDef {
+        name: "test",
+        version: 1,
+        build: v1_schema,
+    };
+    const V2: SchemaDef = SchemaDef {
+        name: "test",
+        version: 2,
+        build: v2_schema,
+    };
+
+    fn docs(index: &Index) -> usize {
+        index.reader().unwrap().searcher().search(&AllQuery, &Count).unwrap()
+    }
+
+    #[test]
+    fn migrates_old_versions_beside_the_live_index() {
+        let dir = std::env::temp_dir().join("aleph-schema-test/index");
+        let _ = fs::remove_dir_all(dir.parent().unwrap());
+
+        let Ok(Opened::Created(index)) = open(&V1, &dir, |_| true) else {
+            panic!("expected a new index");
+        };
+        let path = index.schema().get_field("path").unwrap();
+        let mut writer = index.writer_with_num_threads(1, 15_000_000).unwrap();
+        writer.add_document(doc!(path => "/a")).unwrap();
+        writer.commit().unwrap();
+        drop(writer);
+        drop(index);
+        assert!(matches!(open(&V1, &dir, |_| true), Ok(Opened::Current(_))));
+
+        // Con una versión nueva se sigue leyendo el índice viejo mientras se arma el otro
+        let Ok(Opened::Migrate { old, fresh, fresh_dir }) = open(&V2, &dir, |_| true) else {
+            panic!("expected a migration");
+        };
+        assert_eq!(docs(&old), 1);
+        assert_eq!(docs(&fresh), 0);
+        drop((old, fresh));
+
+        // Sin terminar, la próxima vez se empieza de nuevo; terminada, se promueve
+        assert!(matches!(open(&V2, &dir, |_| true), Ok(Opened::Migrate { .. })));
+        finish(&V2, &fresh_dir).unwrap();
+        assert!(matches!(open(&V2, &dir, |_| true), Ok(Opened::Current(_))));
+        assert!(!fresh_dir.exists());
+        assert_eq!(stored_version(&dir), Some(2));
+
+        // Un índice que no se puede leer con el schema nuevo se rehace en el lugar
+        assert!(matches!(open(&V1, &dir, |_| false), Ok(Opened::Created(_))));
+        let _ = fs::remove_dir_all(dir.parent().unwrap());
+    }
+}
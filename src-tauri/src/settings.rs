@@ -0,0 +1,127 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+// Preferencias generales de Aleph, en ~/.config/aleph/settings.json.
+// Cualquier campo que falte en el archivo toma su valor por defecto
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct Settings {
+    pub content: ContentSettings,
+    pub ranking: RankingProfile,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ContentSettings {
+    // Indexar el texto de los archivos además del nombre
+    pub enabled: bool,
+    // Archivos de texto más grandes que esto se indexan solo por nombre;
+    // también es el máximo de texto que se guarda por documento
+    pub max_bytes: u64,
+    // Límite de tamaño para documentos de los que se extrae texto (PDF, Office)
+    pub max_document_bytes: u64,
+}
+
+impl Default for ContentSettings {
+    fn default() -> Self {
+        ContentSettings {
+            enabled: true,
+            max_bytes: 1024 * 1024,
+            max_document_bytes: 64 * 1024 * 1024,
+        }
+    }
+}
+
+// Pesos del ranking de resultados. Los multiplicadores se aplican sobre el puntaje de
+// tantivy (BM25); `explain_search` muestra cuánto aportó cada uno
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RankingProfile {
+    // Un match fuzzy perfecto en el nombre multiplica por 1 + esto
+    pub name_match_weight: f32,
+    // Lo que vale un match en el resto del path comparado con uno en el nombre
+    pub path_match_factor: f32,
+    // Peso de los términos que aparecen en el contenido frente a los del nombre
+    pub content_boost: f32,
+    // Frecencia: a los cuántos días una apertura vale la mitad, con cuánta frecencia
+    // se llega a la mitad del boost y cuánto puede sumar como máximo
+    pub frecency_half_life_days: f64,
+    pub frecency_half_boost_at: f64,
+    pub frecency_max_boost: f32,
+    // Elecciones por query: lo máximo que suma un resultado siempre elegido, con cuántas
+    // elecciones se confía a medias, y cuánto suma en BM25 para entrar entre los candidatos
+    pub click_max_boost: f32,
+    pub click_half_confidence_at: f64,
+    pub picked_boost: f32,
+    // Archivos a más de tantas carpetas de profundidad se multiplican por la penalidad
+    pub deep_path_depth: usize,
+    pub deep_path_penalty: f32,
+    // Carpetas cuyo nombre matchea la query
+    pub dir_boost: f32,
+    // Extensiones que se prefieren
+    pub boosted_extensions: Vec<String>,
+    pub extension_boost: f32,
+}
+
+impl Default for RankingProfile {
+    fn default() -> Self {
+        RankingProfile {
+            name_match_weight: 2.0,
+            path_match_factor: 0.5,
+            content_boost: 0.5,
+            frecency_half_life_days: 14.0,
+            frecency_half_boost_at: 3.0,
+            frecency_max_boost: 1.0,
+            click_max_boost: 4.0,
+            click_half_confidence_at: 1.0,
+            picked_boost: 5.0,
+            deep_path_depth: 6,
+            deep_path_penalty: 0.8,
+            dir_boost: 1.3,
+            boosted_extensions: vec!["txt".into(), "pdf".into(), "doc".into()],
+            extension_boost: 1.2,
+        }
+    }
+}
+
+fn settings_path() -> Option<PathBuf> {
+    Some(dirs::home_dir()?.join(".config/aleph/settings.json"))
+}
+
+fn load_settings() -> Settings {
+    let raw = match settings_path().and_then(|p| fs::read_to_string(p).ok()) {
+        Some(raw) => raw,
+        None => return Settings::default(),
+    };
+    match serde_json::from_str(&raw) {
+        Ok(settings) => settings,
+        Err(e) => {
+            println!("settings.json inválido ({}), usando valores por defecto", e);
+            Settings::default()
+        }
+    }
+}
+
+// Se lee una sola vez por ejecución
+pub fn settings() -> &'static Settings {
+    static SETTINGS: OnceLock<Settings> = OnceLock::new();
+    SETTINGS.get_or_init(load_settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn partial_ranking_profiles_keep_the_defaults() {
+        let settings: Settings =
+            serde_json::from_str(r#"{"ranking": {"dir_boost": 2.0, "boosted_extensions": ["md"]}}"#)
+                .unwrap();
+        assert_eq!(settings.ranking.dir_boost, 2.0);
+        assert_eq!(settings.ranking.boosted_extensions, vec!["md"]);
+        assert_eq!(settings.ranking.deep_path_penalty, RankingProfile::default().deep_path_penalty);
+        assert!(settings.content.enabled);
+    }
+}
@@ -0,0 +1,336 @@
+use serde::Serialize;
+use std::fs;
+use std::ops::Deref;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant, UNIX_EPOCH};
+use tantivy::IndexReader;
+use tauri::{AppHandle, Emitter};
+
+use crate::apps;
+use crate::error::AlephError;
+use crate::files::{self, RootStats};
+use crate::frecency;
+use crate::schema::SchemaDef;
+
+// Trabajos largos sobre los índices que están corriendo ahora
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobKind {
+    // Indexación completa de una raíz (o de las apps)
+    Index,
+    // Puesta al día de una raíz ya indexada contra el disco
+    Reconcile,
+    // Armado de un índice con un schema nuevo
+    Migrate,
+    Compact,
+}
+
+// Cada cuánto se avisa el avance de un trabajo al frontend
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+// Foto de un trabajo: lo que devuelve index_status y lo que viaja en los eventos
+#[derive(Debug, Clone, Serialize)]
+pub struct Job {
+    pub kind: JobKind,
+    // La raíz o el índice sobre el que corre
+    pub target: String,
+    pub started_at: i64,
+    // Entradas recorridas en disco y documentos escritos al índice
+    pub scanned: u64,
+    pub indexed: u64,
+    pub current_dir: Option<String>,
+    // Segundos que faltan, si se sabe cuánto hay que hacer
+    pub eta_secs: Option<u64>,
+}
+
+// Evento final de un trabajo (`error` es None si terminó bien)
+#[derive(Debug, Clone, Serialize)]
+pub struct JobDone {
+    #[serde(flatten)]
+    pub job: Job,
+    pub elapsed_ms: u64,
+    pub error: Option<String>,
+}
+
+struct JobState {
+    id: u64,
+    kind: JobKind,
+    target: String,
+    started_at: i64,
+    started: Instant,
+    // Cuántos documentos se esperan (p. ej. los que tenía la raíz la vez anterior)
+    expected: AtomicU64,
+    scanned: AtomicU64,
+    indexed: AtomicU64,
+    current_dir: Mutex<Option<String>>,
+    last_emit: Mutex<Instant>,
+}
+
+impl JobState {
+    fn snapshot(&self) -> Job {
+        let indexed = self.indexed.load(Ordering::Relaxed);
+        let expected = self.expected.load(Ordering::Relaxed);
+        let elapsed = self.started.elapsed().as_secs_f64();
+        let eta_secs = (expected > 0 && indexed > 0).then(|| {
+            let rate = indexed as f64 / elapsed.max(0.001);
+            (expected.saturating_sub(indexed) as f64 / rate).round() as u64
+        });
+        Job {
+            kind: self.kind,
+            target: self.target.clone(),
+            started_at: self.started_at,
+            scanned: self.scanned.load(Ordering::Relaxed),
+            indexed,
+            current_dir: self.current_dir.lock().unwrap().clone(),
+            eta_secs,
+        }
+    }
+}
+
+fn registry() -> &'static Mutex<(u64, Vec<Arc<JobState>>)> {
+    static JOBS: OnceLock<Mutex<(u64, Vec<Arc<JobState>>)>> = OnceLock::new();
+    JOBS.get_or_init(|| Mutex::new((0, Vec::new())))
+}
+
+// Para mandar eventos al frontend; se guarda al arrancar la app
+fn app_handle() -> &'static OnceLock<AppHandle> {
+    static APP: OnceLock<AppHandle> = OnceLock::new();
+    &APP
+}
+
+pub fn set_app_handle(app: AppHandle) {
+    let _ = app_handle().set(app);
+}
+
+pub fn emit<S: Serialize + Clone>(event: &str, payload: S) {
+    if let Some(app) = app_handle().get() {
+        if let Err(e) = app.emit(event, payload) {
+            println!("Error emitting {}: {}", event, e);
+        }
+    }
+}
+
+// Un trabajo queda registrado mientras viva su guard. Se comparte entre los hilos que
+// indexan, que van contando lo que hacen; el avance sale como evento `index-progress`
+// y el final como `index-ready`
+pub struct JobGuard {
+    progress: JobProgress,
+}
+
+// Lo que cuentan los hilos que hacen el trabajo; se puede mover a otro hilo (p. ej.
+// con spawn_blocking) mientras el guard queda con quien lo empezó
+#[derive(Clone)]
+pub struct JobProgress {
+    state: Arc<JobState>,
+}
+
+impl JobProgress {
+    pub fn expect(&self, total: u64) {
+        self.state.expected.store(total, Ordering::Relaxed);
+    }
+
+    // Una entrada recorrida; las carpetas pasan a ser la carpeta actual
+    pub fn scanned(&self, path: &Path, is_dir: bool) {
+        self.state.scanned.fetch_add(1, Ordering::Relaxed);
+        if is_dir {
+            *self.state.current_dir.lock().unwrap() = Some(path.display().to_string());
+        }
+        self.maybe_emit();
+    }
+
+    pub fn indexed(&self, count: u64) {
+        self.state.indexed.fetch_add(count, Ordering::Relaxed);
+        self.maybe_emit();
+    }
+
+    fn maybe_emit(&self) {
+        {
+            let mut last = self.state.last_emit.lock().unwrap();
+            if last.elapsed() < PROGRESS_INTERVAL {
+                return;
+            }
+            *last = Instant::now();
+        }
+        emit("index-progress", self.state.snapshot());
+    }
+}
+
+impl Deref for JobGuard {
+    type Target = JobProgress;
+
+    fn deref(&self) -> &JobProgress {
+        &self.progress
+    }
+}
+
+impl JobGuard {
+    pub fn progress(&self) -> JobProgress {
+        self.progress.clone()
+    }
+
+    // Avisa que terminó (bien o con error); el registro se limpia al soltar el guard
+    pub fn finish(self, result: &Result<(), String>) {
+        let state = &self.progress.state;
+        emit(
+            "index-ready",
+            JobDone {
+                job: state.snapshot(),
+                elapsed_ms: state.started.elapsed().as_millis() as u64,
+                error: result.as_ref().err().cloned(),
+            },
+        );
+    }
+}
+
+impl Drop for JobGuard {
+    fn drop(&mut self) {
+        registry()
+            .lock()
+            .unwrap()
+            .1
+            .retain(|job| job.id != self.progress.state.id);
+    }
+}
+
+pub fn start_job(kind: JobKind, target: impl Into<String>) -> JobGuard {
+    let mut jobs = registry().lock().unwrap();
+    jobs.0 += 1;
+    let now = Instant::now();
+    let state = Arc::new(JobState {
+        id: jobs.0,
+        kind,
+        target: target.into(),
+        started_at: frecency::unix_now(),
+        started: now,
+        expected: AtomicU64::new(0),
+        scanned: AtomicU64::new(0),
+        indexed: AtomicU64::new(0),
+        current_dir: Mutex::new(None),
+        last_emit: Mutex::new(now),
+    });
+    jobs.1.push(state.clone());
+    JobGuard {
+        progress: JobProgress { state },
+    }
+}
+
+pub fn running_jobs() -> Vec<Job> {
+    registry()
+        .lock()
+        .unwrap()
+        .1
+        .iter()
+        .map(|job| job.snapshot())
+        .collect()
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct IndexStats {
+    pub name: &'static str,
+    pub version: u32,
+    pub dir: String,
+    pub docs: u64,
+    pub segments: usize,
+    pub size_bytes: u64,
+    // Segundos unix del último commit (cuando se escribió el meta.json)
+    pub last_commit: Option<i64>,
+}
+
+pub fn index_stats(def: &SchemaDef, reader: &IndexReader, dir: &Path) -> IndexStats {
+    let searcher = reader.searcher();
+    // tantivy guarda todo plano en la carpeta del índice
+    let size_bytes = fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter_map(|e| e.metadata().ok())
+                .filter(|m| m.is_file())
+                .map(|m| m.len())
+                .sum()
+        })
+        .unwrap_or(0);
+    let last_commit = fs::metadata(dir.join("meta.json"))
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_secs() as i64);
+
+    IndexStats {
+        name: def.name,
+        version: def.version,
+        dir: dir.display().to_string(),
+        docs: searcher.num_docs(),
+        segments: searcher.segment_readers().len(),
+        size_bytes,
+        last_commit,
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct IndexStatus {
+    pub files: IndexStats,
+    pub apps: IndexStats,
+    pub roots: Vec<RootStats>,
+    pub jobs: Vec<Job>,
+}
+
+#[tauri::command]
+pub async fn index_status() -> Result<IndexStatus, AlephError> {
+    Ok(IndexStatus {
+        files: files::stats()?,
+        apps: apps::stats()?,
+        roots: files::root_stats()?,
+        jobs: running_jobs(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn jobs_live_as_long_as_their_guard() {
+        let target = "/tmp/aleph-status-test";
+        let of_target = || {
+            running_jobs()
+                .into_iter()
+                .filter(|job| job.target == target)
+                .map(|job| job.kind)
+                .collect::<Vec<_>>()
+        };
+
+        let index = start_job(JobKind::Index, target);
+        let compact = start_job(JobKind::Compact, target);
+        assert_eq!(of_target(), vec![JobKind::Index, JobKind::Compact]);
+        drop(index);
+        assert_eq!(of_target(), vec![JobKind::Compact]);
+        drop(compact);
+        assert!(of_target().is_empty());
+    }
+
+    #[test]
+    fn counts_progress_and_estimates_what_is_left() {
+        let target = "/tmp/aleph-progress-test";
+        let job = start_job(JobKind::Index, target);
+        job.expect(10);
+        job.scanned(Path::new("/tmp/aleph-progress-test/sub"), true);
+        job.scanned(Path::new("/tmp/aleph-progress-test/sub/a.txt"), false);
+        job.indexed(2);
+
+        let snapshot = running_jobs()
+            .into_iter()
+            .find(|j| j.target == target)
+            .unwrap();
+        assert_eq!(snapshot.scanned, 2);
+        assert_eq!(snapshot.indexed, 2);
+        assert_eq!(
+            snapshot.current_dir.as_deref(),
+            Some("/tmp/aleph-progress-test/sub")
+        );
+        assert!(snapshot.eta_secs.is_some());
+        job.finish(&Ok(()));
+        assert!(running_jobs().iter().all(|j| j.target != target));
+    }
+}
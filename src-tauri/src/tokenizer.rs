@@ -0,0 +1,521 @@
+use std::borrow::Cow;
+use tantivy::query::{BooleanQuery, BoostQuery, Occur, Query, TermQuery};
+use tantivy::schema::{Field, IndexRecordOption, TextFieldIndexing, TextOptions};
+use tantivy::tokenizer::{RemoveLongFilter, TextAnalyzer, Token, TokenStream, Tokenizer};
+use tantivy::{Index, Term};
+use unicode_normalization::{is_nfc, UnicodeNormalization};
+
+// Tokenizers de nombres de archivo: cortan identificadores (camelCase, snake_case,
+// kebab-case y letras/dígitos) y, para el field de prefijos, arman los edge n-grams
+// de cada parte. Así `repfin` encuentra MyReportFinal_v2.docx sin un regex sobre el path.
+// Todos pliegan mayúsculas y acentos: `cancion` encuentra canción.mp3
+pub const IDENT: &str = "aleph_ident";
+pub const IDENT_PREFIX: &str = "aleph_ident_prefix";
+// Texto corrido (contenido y metadatos): solo palabras plegadas
+pub const TEXT: &str = "aleph_text";
+
+// Palabras más largas que esto en el texto corrido son ruido (hashes, base64)
+const MAX_WORD_BYTES: usize = 40;
+
+// Prefijos más largos que esto no se indexan
+const MAX_PREFIX: usize = 20;
+// Una palabra de la query se prueba partida en hasta tantos prefijos
+const MAX_PIECES: usize = 3;
+// Palabras más largas se buscan solo como prefijo entero (las particiones crecen rápido)
+const MAX_SPLIT_LEN: usize = 12;
+
+pub fn register(index: &Index) {
+    let tokenizers = index.tokenizers();
+    tokenizers.register(IDENT, TextAnalyzer::from(NameTokenizer { mode: Mode::Ident }));
+    tokenizers.register(
+        IDENT_PREFIX,
+        TextAnalyzer::from(NameTokenizer {
+            mode: Mode::Prefixes,
+        }),
+    );
+    tokenizers.register(
+        TEXT,
+        TextAnalyzer::builder(NameTokenizer { mode: Mode::Words })
+            .filter(RemoveLongFilter::limit(MAX_WORD_BYTES))
+            .build(),
+    );
+}
+
+// Los nombres que vienen de macOS suelen estar descompuestos (NFD: "o" + acento
+// combinado); se recomponen para que las palabras no se corten en el acento
+pub fn nfc(text: &str) -> Cow<'_, str> {
+    if is_nfc(text) {
+        Cow::Borrowed(text)
+    } else {
+        Cow::Owned(text.nfc().collect())
+    }
+}
+
+// Minúsculas y sin acentos ni diacríticos latinos (á -> a, ñ -> n, ü -> u), en
+// cualquier normalización de entrada. Es lo que se compara en el índice y en el scorer
+pub fn fold(text: &str) -> String {
+    text.nfkd()
+        .filter(|c| !('\u{0300}'..='\u{036f}').contains(c))
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+// Texto corrido (contenido y metadatos de documentos)
+pub fn text_options() -> TextOptions {
+    TextOptions::default().set_indexing_options(
+        TextFieldIndexing::default()
+            .set_tokenizer(TEXT)
+            .set_index_option(IndexRecordOption::WithFreqsAndPositions),
+    )
+}
+
+// Field de nombre: partes y palabra entera, con posiciones para las frases
+pub fn ident_options() -> TextOptions {
+    TextOptions::default().set_indexing_options(
+        TextFieldIndexing::default()
+            .set_tokenizer(IDENT)
+            .set_index_option(IndexRecordOption::WithFreqsAndPositions),
+    )
+}
+
+// Subfield de prefijos del nombre (no se guarda: es el mismo texto que el nombre)
+pub fn prefix_options() -> TextOptions {
+    TextOptions::default().set_indexing_options(
+        TextFieldIndexing::default()
+            .set_tokenizer(IDENT_PREFIX)
+            .set_index_option(IndexRecordOption::WithFreqs),
+    )
+}
+
+// Corta una palabra (solo letras/dígitos) en sus partes: "MyHTMLParser2" ->
+// My, HTML, Parser, 2. Devuelve rangos en bytes
+fn split_parts(word: &str) -> Vec<(usize, usize)> {
+    let chars: Vec<(usize, char)> = word.char_indices().collect();
+    let mut parts = Vec::new();
+    let mut start = 0;
+    for i in 1..chars.len() {
+        let (prev, cur) = (chars[i - 1].1, chars[i].1);
+        let next = chars.get(i + 1).map(|&(_, c)| c);
+        let boundary = (prev.is_lowercase() && cur.is_uppercase())
+            || (prev.is_numeric() != cur.is_numeric())
+            // El final de una sigla: HTMLParser -> HTML | Parser
+            || (prev.is_uppercase()
+                && cur.is_uppercase()
+                && next.is_some_and(|n| n.is_lowercase()));
+        if boundary {
+            parts.push((start, chars[i].0));
+            start = chars[i].0;
+        }
+    }
+    if !chars.is_empty() {
+        parts.push((start, word.len()));
+    }
+    parts
+}
+
+// Chino, japonés y coreano: se escriben sin espacios, así que una corrida entera
+// sería una sola palabra. Se indexan de a pares de caracteres (bigramas)
+fn is_cjk(c: char) -> bool {
+    matches!(c,
+        '\u{1100}'..='\u{11ff}' // Hangul jamo
+        | '\u{3040}'..='\u{30ff}' // Hiragana y katakana
+        | '\u{3130}'..='\u{318f}' // Hangul jamo de compatibilidad
+        | '\u{31f0}'..='\u{31ff}' // Extensiones fonéticas de katakana
+        | '\u{3400}'..='\u{4dbf}' // Han, extensión A
+        | '\u{4e00}'..='\u{9fff}' // Han
+        | '\u{ac00}'..='\u{d7af}' // Sílabas hangul
+        | '\u{f900}'..='\u{faff}' // Han de compatibilidad
+        | '\u{ff65}'..='\u{ff9f}' // Katakana de ancho medio
+        | '\u{20000}'..='\u{2fa1f}' // Han, extensiones B en adelante
+    )
+}
+
+fn is_cjk_word(word: &str) -> bool {
+    word.chars().next().is_some_and(is_cjk)
+}
+
+// Con bigramas una distancia de edición de 1 matchea casi cualquier cosa: las queries
+// con CJK no usan fuzzy
+pub fn has_cjk(text: &str) -> bool {
+    text.chars().any(is_cjk)
+}
+
+// Palabras del texto (corridas de letras/dígitos) con su offset. Las corridas CJK
+// van aparte: "報告書v2" -> 報告書, v2
+fn words(text: &str) -> Vec<(usize, &str)> {
+    let mut words = Vec::new();
+    let mut start: Option<(usize, bool)> = None;
+    for (i, c) in text.char_indices() {
+        let cjk = is_cjk(c);
+        match (c.is_alphanumeric(), start) {
+            (true, None) => start = Some((i, cjk)),
+            (true, Some((s, was_cjk))) if was_cjk != cjk => {
+                words.push((s, &text[s..i]));
+                start = Some((i, cjk));
+            }
+            (false, Some((s, _))) => {
+                words.push((s, &text[s..i]));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some((s, _)) = start {
+        words.push((s, &text[s..]));
+    }
+    words
+}
+
+// Bigramas de una palabra CJK como rangos en bytes: 東京都 -> 東京, 京都.
+// Un solo carácter queda como está
+fn bigrams(word: &str) -> Vec<(usize, usize)> {
+    let bounds: Vec<usize> = word
+        .char_indices()
+        .map(|(i, _)| i)
+        .chain(std::iter::once(word.len()))
+        .collect();
+    if bounds.len() <= 2 {
+        return vec![(0, word.len())];
+    }
+    bounds.windows(3).map(|w| (w[0], w[2])).collect()
+}
+
+fn edge_ngrams(text: &str) -> impl Iterator<Item = &str> {
+    text.char_indices()
+        .skip(1)
+        .map(|(i, _)| i)
+        .chain(std::iter::once(text.len()))
+        .take(MAX_PREFIX)
+        .map(move |end| &text[..end])
+}
+
+#[derive(Clone, Copy)]
+enum Mode {
+    // Palabras enteras
+    Words,
+    // Palabras y sus partes de identificador
+    Ident,
+    // Prefijos de las palabras y de sus partes
+    Prefixes,
+}
+
+#[derive(Clone)]
+struct NameTokenizer {
+    mode: Mode,
+}
+
+impl NameTokenizer {
+    // Los offsets son sobre el texto en NFC (el contenido se guarda así para los snippets)
+    fn tokens(&self, text: &str) -> Vec<Token> {
+        let text = nfc(text);
+        let mut tokens = Vec::new();
+        let mut position = 0;
+        let push = |tokens: &mut Vec<Token>, from: usize, to: usize, text: String, position: usize| {
+            tokens.push(Token {
+                offset_from: from,
+                offset_to: to,
+                position,
+                text,
+                position_length: 1,
+            });
+        };
+
+        for (offset, word) in words(&text) {
+            if is_cjk_word(word) {
+                for (from, to) in bigrams(word) {
+                    push(&mut tokens, offset + from, offset + to, fold(&word[from..to]), position);
+                    position += 1;
+                }
+                // Los prefijos de una letra dejan encontrar nombres buscando un solo carácter
+                if let Mode::Prefixes = self.mode {
+                    for (from, c) in word.char_indices() {
+                        let to = from + c.len_utf8();
+                        push(&mut tokens, offset + from, offset + to, fold(&word[from..to]), position);
+                    }
+                }
+                continue;
+            }
+            let folded = fold(word);
+            match self.mode {
+                Mode::Words => {
+                    push(&mut tokens, offset, offset + word.len(), folded, position);
+                    position += 1;
+                }
+                Mode::Prefixes => {
+                    for prefix in edge_ngrams(&folded) {
+                        push(&mut tokens, offset, offset + word.len(), prefix.to_string(), position);
+                    }
+                    let parts = split_parts(word);
+                    if parts.len() > 1 {
+                        for &(from, to) in &parts[1..] {
+                            for prefix in edge_ngrams(&fold(&word[from..to])) {
+                                push(&mut tokens, offset + from, offset + to, prefix.to_string(), position);
+                            }
+                        }
+                    }
+                    position += 1;
+                }
+                Mode::Ident => {
+                    // La palabra entera va en la posición de su primera parte, así "myreport"
+                    // y "my report" matchean los dos
+                    push(&mut tokens, offset, offset + word.len(), folded, position);
+                    let parts = split_parts(word);
+                    if parts.len() > 1 {
+                        for (from, to) in parts {
+                            push(&mut tokens, offset + from, offset + to, fold(&word[from..to]), position);
+                            position += 1;
+                        }
+                    } else {
+                        position += 1;
+                    }
+                }
+            }
+        }
+        tokens
+    }
+}
+
+impl Tokenizer for NameTokenizer {
+    type TokenStream<'a> = VecTokenStream;
+
+    fn token_stream<'a>(&'a mut self, text: &'a str) -> VecTokenStream {
+        VecTokenStream {
+            tokens: self.tokens(text),
+            index: None,
+        }
+    }
+}
+
+pub struct VecTokenStream {
+    tokens: Vec<Token>,
+    index: Option<usize>,
+}
+
+impl TokenStream for VecTokenStream {
+    fn advance(&mut self) -> bool {
+        let next = self.index.map_or(0, |i| i + 1);
+        self.index = Some(next);
+        next < self.tokens.len()
+    }
+
+    fn token(&self) -> &Token {
+        &self.tokens[self.index.unwrap_or(0)]
+    }
+
+    fn token_mut(&mut self) -> &mut Token {
+        &mut self.tokens[self.index.unwrap_or(0)]
+    }
+}
+
+// Formas de leer una palabra de la query como prefijos de partes seguidas del nombre:
+// "repfin" -> [repfin], [re, pfin], [rep, fin], [repf, in], [re, pf, in]
+fn abbreviations(word: &str) -> Vec<Vec<String>> {
+    let chars: Vec<char> = word.chars().collect();
+    let mut result = vec![vec![word.to_string()]];
+    if chars.len() > MAX_SPLIT_LEN {
+        return result;
+    }
+    // Con palabras cortas (siglas como "vsc") cada letra puede ser una parte
+    let min_piece = if chars.len() <= 4 { 1 } else { 2 };
+
+    fn split(
+        chars: &[char],
+        min_piece: usize,
+        pieces_left: usize,
+        current: &mut Vec<String>,
+        result: &mut Vec<Vec<String>>,
+    ) {
+        if chars.is_empty() {
+            if current.len() > 1 {
+                result.push(current.clone());
+            }
+            return;
+        }
+        if pieces_left == 0 {
+            return;
+        }
+        for len in min_piece..=chars.len() {
+            // Un solo pedazo es la palabra entera, que ya está
+            if current.is_empty() && len == chars.len() {
+                continue;
+            }
+            current.push(chars[..len].iter().collect());
+            split(&chars[len..], min_piece, pieces_left - 1, current, result);
+            current.pop();
+        }
+    }
+    split(&chars, min_piece, MAX_PIECES, &mut Vec::new(), &mut result);
+    result
+}
+
+// Query sobre el field de prefijos: cada palabra tiene que matchear como prefijo de
+// una parte del nombre o como abreviatura de varias. El prefijo directo pesa más
+pub fn abbreviation_query(field: Field, text: &str) -> Option<Box<dyn Query>> {
+    let text = nfc(text);
+    let words: Vec<&str> = words(&text)
+        .into_iter()
+        .map(|(_, w)| w)
+        .filter(|w| is_cjk_word(w) || w.chars().count() <= MAX_PREFIX)
+        .collect();
+    if words.is_empty() {
+        return None;
+    }
+    let term = |piece: &str| -> (Occur, Box<dyn Query>) {
+        let term = TermQuery::new(Term::from_field_text(field, piece), IndexRecordOption::WithFreqs);
+        (Occur::Must, Box::new(term))
+    };
+
+    let must = words
+        .iter()
+        .map(|&word| {
+            // En CJK no hay partes ni abreviaturas: tienen que estar todos sus bigramas
+            if is_cjk_word(word) {
+                let grams = bigrams(word)
+                    .into_iter()
+                    .map(|(from, to)| term(&fold(&word[from..to])))
+                    .collect();
+                return (Occur::Must, Box::new(BooleanQuery::new(grams)) as Box<dyn Query>);
+            }
+            let should = abbreviations(&fold(word))
+                .into_iter()
+                .map(|pieces| {
+                    let whole = pieces.len() == 1;
+                    let terms = pieces.iter().map(|piece| term(piece)).collect();
+                    let query: Box<dyn Query> = Box::new(BooleanQuery::new(terms));
+                    let query: Box<dyn Query> = if whole {
+                        Box::new(BoostQuery::new(query, 2.0))
+                    } else {
+                        query
+                    };
+                    (Occur::Should, query)
+                })
+                .collect();
+            (Occur::Must, Box::new(BooleanQuery::new(should)) as Box<dyn Query>)
+        })
+        .collect();
+    Some(Box::new(BooleanQuery::new(must)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tantivy::collector::TopDocs;
+    use tantivy::doc;
+    use tantivy::schema::{Schema, Value};
+    use tantivy::TantivyDocument;
+
+    fn texts(mode: Mode, text: &str) -> Vec<String> {
+        NameTokenizer { mode }
+            .tokens(text)
+            .into_iter()
+            .map(|t| t.text)
+            .collect()
+    }
+
+    #[test]
+    fn splits_identifiers() {
+        let parts = |word: &str| {
+            split_parts(word)
+                .into_iter()
+                .map(|(a, b)| word[a..b].to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(parts("MyReportFinal"), vec!["My", "Report", "Final"]);
+        assert_eq!(parts("HTMLParser2"), vec!["HTML", "Parser", "2"]);
+        assert_eq!(parts("v2"), vec!["v", "2"]);
+        assert_eq!(parts("año"), vec!["año"]);
+
+        assert_eq!(
+            texts(Mode::Ident, "MyReportFinal_v2.docx"),
+            vec!["myreportfinal", "my", "report", "final", "v2", "v", "2", "docx"]
+        );
+        let prefixes = texts(Mode::Prefixes, "MyReport.md");
+        for expected in ["m", "myrep", "rep", "report", "md"] {
+            assert!(prefixes.contains(&expected.to_string()), "{}", expected);
+        }
+    }
+
+    #[test]
+    fn folds_accents_case_and_decomposed_names() {
+        assert_eq!(fold("Canción"), "cancion");
+        assert_eq!(fold("AÑO_2024"), "ano_2024");
+        // macOS: "canción" con el acento como carácter aparte
+        let decomposed = "cancio\u{301}n.mp3";
+        assert_eq!(texts(Mode::Ident, decomposed), vec!["cancion", "mp3", "mp", "3"]);
+        assert_eq!(
+            texts(Mode::Words, "Él dijo: ¡Año Nuevo!"),
+            vec!["el", "dijo", "ano", "nuevo"]
+        );
+    }
+
+    #[test]
+    fn splits_cjk_into_bigrams() {
+        assert_eq!(
+            texts(Mode::Ident, "東京都の地図v2.png"),
+            vec!["東京", "京都", "都の", "の地", "地図", "v2", "v", "2", "png"]
+        );
+        let prefixes = texts(Mode::Prefixes, "報告書");
+        for expected in ["報告", "告書", "報", "告", "書"] {
+            assert!(prefixes.contains(&expected.to_string()), "{}", expected);
+        }
+        assert_eq!(texts(Mode::Words, "天気"), vec!["天気"]);
+    }
+
+    #[test]
+    fn abbreviations_split_into_prefixes() {
+        let splits = abbreviations("repfin");
+        assert!(splits.contains(&vec!["repfin".to_string()]));
+        assert!(splits.contains(&vec!["rep".to_string(), "fin".to_string()]));
+        assert!(splits.iter().all(|s| s.len() <= MAX_PIECES));
+        assert!(abbreviations("vsc").contains(&vec![
+            "v".to_string(),
+            "s".to_string(),
+            "c".to_string()
+        ]));
+    }
+
+    #[test]
+    fn finds_files_by_abbreviation() {
+        let mut builder = Schema::builder();
+        let name = builder.add_text_field("name", ident_options().set_stored());
+        let name_ngram = builder.add_text_field("name_ngram", prefix_options());
+        let index = Index::create_in_ram(builder.build());
+        register(&index);
+        let mut writer = index.writer_with_num_threads(1, 15_000_000).unwrap();
+        for file in [
+            "MyReportFinal_v2.docx",
+            "notes.txt",
+            "Visual Studio Code.app",
+            "東京都の地図.png",
+        ] {
+            writer
+                .add_document(doc!(name => file, name_ngram => file))
+                .unwrap();
+        }
+        writer.commit().unwrap();
+        let searcher = index.reader().unwrap().searcher();
+
+        let find = |text: &str| -> Vec<String> {
+            let query = abbreviation_query(name_ngram, text).unwrap();
+            searcher
+                .search(&query, &TopDocs::with_limit(5))
+                .unwrap()
+                .into_iter()
+                .map(|(_, address)| {
+                    let doc: TantivyDocument = searcher.doc(address).unwrap();
+                    doc.get_first(name)
+                        .and_then(|v| v.as_str())
+                        .unwrap()
+                        .to_string()
+                })
+                .collect()
+        };
+        assert_eq!(find("repfin"), vec!["MyReportFinal_v2.docx"]);
+        assert_eq!(find("vsc"), vec!["Visual Studio Code.app"]);
+        assert_eq!(find("final v2"), vec!["MyReportFinal_v2.docx"]);
+        assert!(find("xyz").is_empty());
+        assert_eq!(find("京都"), vec!["東京都の地図.png"]);
+        assert_eq!(find("地"), vec!["東京都の地図.png"]);
+        assert!(find("京地").is_empty());
+    }
+}
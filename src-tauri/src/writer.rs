@@ -0,0 +1,297 @@
+use std::collections::HashMap;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::thread;
+use std::time::{Duration, Instant};
+use tantivy::{Index, IndexWriter, TantivyDocument, Term};
+use tokio::sync::oneshot;
+
+// Los cambios del watcher se commitean a lo sumo COMMIT_INTERVAL después del primero
+// pendiente, o antes si se juntan MAX_PENDING. Las altas masivas (Add) no ponen plazo:
+// van con el commit explícito del final del trabajo o con el próximo del watcher
+const COMMIT_INTERVAL: Duration = Duration::from_secs(2);
+const MAX_PENDING: usize = 1_000;
+
+pub enum Change {
+    // Reemplaza los documentos que tengan `key` (el path) por `doc`
+    Upsert(Term, TantivyDocument),
+    // Borra todos los documentos que tengan el término
+    Delete(Term),
+}
+
+enum WriteOp {
+    // Cambios que tienen que quedar en el mismo commit (p. ej. las dos mitades de un move)
+    Changes(Vec<Change>),
+    // Alta sin reemplazo, para la indexación masiva
+    Add(TantivyDocument),
+    // Borrado que abre una indexación masiva, antes de sus altas
+    Clear(Term),
+    // Commit inmediato; responde cuando quedó en disco
+    Commit(oneshot::Sender<Result<(), String>>),
+    // Commit y merge de todos los segmentos en uno, sacando los documentos borrados
+    Compact(oneshot::Sender<Result<(), String>>),
+}
+
+// Un único IndexWriter por índice, vivo durante toda la ejecución en su propio hilo.
+// Todo el que quiera escribir manda mensajes por el canal: así no hay dos writers
+// peleando por el lock del índice y una ráfaga de eventos termina en un solo commit
+#[derive(Clone)]
+pub struct WriterHandle {
+    tx: Sender<WriteOp>,
+}
+
+impl WriterHandle {
+    pub fn spawn(index: &Index, name: &str) -> Result<Self, String> {
+        let writer: IndexWriter = index
+            .writer_with_num_threads(8, 200_000_000)
+            .map_err(|e| e.to_string())?;
+        let (tx, rx) = mpsc::channel();
+        thread::Builder::new()
+            .name(format!("aleph-writer-{}", name))
+            .spawn(move || run(writer, rx))
+            .map_err(|e| e.to_string())?;
+        Ok(WriterHandle { tx })
+    }
+
+    fn send(&self, op: WriteOp) -> Result<(), String> {
+        self.tx
+            .send(op)
+            .map_err(|_| "el writer del índice se cerró".to_string())
+    }
+
+    pub fn upsert(&self, key: Term, doc: TantivyDocument) -> Result<(), String> {
+        self.apply(vec![Change::Upsert(key, doc)])
+    }
+
+    pub fn delete(&self, term: Term) -> Result<(), String> {
+        self.apply(vec![Change::Delete(term)])
+    }
+
+    // Los cambios se aplican juntos: nunca queda un commit entre medio
+    pub fn apply(&self, changes: Vec<Change>) -> Result<(), String> {
+        if changes.is_empty() {
+            return Ok(());
+        }
+        self.send(WriteOp::Changes(changes))
+    }
+
+    pub fn add(&self, doc: TantivyDocument) -> Result<(), String> {
+        self.send(WriteOp::Add(doc))
+    }
+
+    // Borra lo que tenga el término antes de volver a agregarlo con `add`: hasta el
+    // commit del final se sigue viendo lo anterior
+    pub fn clear(&self, term: Term) -> Result<(), String> {
+        self.send(WriteOp::Clear(term))
+    }
+
+    // Commitea todo lo que se mandó hasta ahora y espera a que termine
+    pub async fn commit(&self) -> Result<(), String> {
+        let (reply, done) = oneshot::channel();
+        self.send(WriteOp::Commit(reply))?;
+        done.await
+            .map_err(|_| "el writer del índice se cerró".to_string())?
+    }
+
+    // Commitea y compacta el índice entero; espera a que termine el merge
+    pub async fn compact(&self) -> Result<(), String> {
+        let (reply, done) = oneshot::channel();
+        self.send(WriteOp::Compact(reply))?;
+        done.await
+            .map_err(|_| "el writer del índice se cerró".to_string())?
+    }
+}
+
+fn compact(writer: &mut IndexWriter) -> Result<(), String> {
+    let segments = writer
+        .index()
+        .searchable_segment_ids()
+        .map_err(|e| e.to_string())?;
+    if segments.len() > 1 {
+        writer.merge(&segments).wait().map_err(|e| e.to_string())?;
+    }
+    writer
+        .garbage_collect_files()
+        .wait()
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+fn run(mut writer: IndexWriter, rx: Receiver<WriteOp>) {
+    let mut batch = Batch::default();
+    loop {
+        let msg = match batch.deadline {
+            Some(deadline) => rx.recv_timeout(deadline.saturating_duration_since(Instant::now())),
+            None => rx.recv().map_err(|_| RecvTimeoutError::Disconnected),
+        };
+        match msg {
+            Ok(WriteOp::Commit(reply)) => {
+                let _ = reply.send(batch.commit(&mut writer));
+            }
+            Ok(WriteOp::Compact(reply)) => {
+                let _ = reply.send(batch.commit(&mut writer).and_then(|_| compact(&mut writer)));
+            }
+            Ok(op) => {
+                batch.push(&writer, op);
+                if batch.pending.len() >= MAX_PENDING {
+                    batch.flush(&mut writer);
+                }
+            }
+            Err(RecvTimeoutError::Timeout) => batch.flush(&mut writer),
+            Err(RecvTimeoutError::Disconnected) => {
+                batch.flush(&mut writer);
+                break;
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+struct Batch {
+    // Upserts/deletes sin aplicar, en orden; los reemplazados por uno posterior quedan en None
+    pending: Vec<Option<Change>>,
+    by_key: HashMap<Term, usize>,
+    // Hay cambios aplicados al writer que falta commitear
+    dirty: bool,
+    deadline: Option<Instant>,
+}
+
+impl Batch {
+    fn push(&mut self, writer: &IndexWriter, op: WriteOp) {
+        match op {
+            WriteOp::Changes(changes) => {
+                if self.deadline.is_none() {
+                    self.deadline = Some(Instant::now() + COMMIT_INTERVAL);
+                }
+                for change in changes {
+                    // Todo cambio empieza borrando su clave, así que uno posterior sobre
+                    // la misma clave deja sin efecto al anterior (p. ej. guardados seguidos)
+                    let key = match &change {
+                        Change::Upsert(key, _) | Change::Delete(key) => key.clone(),
+                    };
+                    if let Some(previous) = self.by_key.insert(key, self.pending.len()) {
+                        self.pending[previous] = None;
+                    }
+                    self.pending.push(Some(change));
+                }
+            }
+            WriteOp::Add(doc) => {
+                // Lo pendiente va antes, para respetar el orden
+                self.apply(writer);
+                let _ = writer.add_document(doc);
+                self.dirty = true;
+            }
+            WriteOp::Clear(term) => {
+                self.apply(writer);
+                writer.delete_term(term);
+                self.dirty = true;
+            }
+            WriteOp::Commit(_) | WriteOp::Compact(_) => {}
+        }
+    }
+
+    fn apply(&mut self, writer: &IndexWriter) {
+        for change in self.pending.drain(..).flatten() {
+            match change {
+                Change::Upsert(key, doc) => {
+                    writer.delete_term(key);
+                    let _ = writer.add_document(doc);
+                }
+                Change::Delete(term) => {
+                    writer.delete_term(term);
+                }
+            }
+            self.dirty = true;
+        }
+        self.by_key.clear();
+    }
+
+    fn commit(&mut self, writer: &mut IndexWriter) -> Result<(), String> {
+        self.apply(writer);
+        self.deadline = None;
+        if !self.dirty {
+            return Ok(());
+        }
+        self.dirty = false;
+        writer.commit().map(|_| ()).map_err(|e| e.to_string())
+    }
+
+    // Commit por tiempo o por cantidad. Si hay una indexación masiva a medias, lo que
+    // ya agregó se commitea también: los cambios del watcher no esperan a que termine
+    fn flush(&mut self, writer: &mut IndexWriter) {
+        if let Err(e) = self.commit(writer) {
+            println!("Error committing index: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tantivy::collector::Count;
+    use tantivy::query::AllQuery;
+    use tantivy::schema::{Schema, STORED, STRING};
+    use tantivy::doc;
+
+    #[test]
+    fn coalesces_and_commits_in_order() {
+        let mut builder = Schema::builder();
+        let path = builder.add_text_field("path", STRING | STORED);
+        let index = Index::create_in_ram(builder.build());
+        let reader = index.reader().unwrap();
+        let writer = WriterHandle::spawn(&index, "test").unwrap();
+        let key = |p: &str| Term::from_field_text(path, p);
+
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async {
+            writer.add(doc!(path => "/a")).unwrap();
+            writer.add(doc!(path => "/b")).unwrap();
+            // varias actualizaciones seguidas del mismo path dejan un solo documento
+            for _ in 0..5 {
+                writer.apply(vec![Change::Upsert(key("/c"), doc!(path => "/c"))]).unwrap();
+            }
+            writer.delete(key("/a")).unwrap();
+            writer.commit().await.unwrap();
+        });
+
+        reader.reload().unwrap();
+        let searcher = reader.searcher();
+        assert_eq!(searcher.search(&AllQuery, &Count).unwrap(), 2);
+        let count = |p: &str| {
+            let query = tantivy::query::TermQuery::new(
+                key(p),
+                tantivy::schema::IndexRecordOption::Basic,
+            );
+            searcher.search(&query, &Count).unwrap()
+        };
+        assert_eq!(count("/a"), 0);
+        assert_eq!(count("/c"), 1);
+    }
+
+    #[test]
+    fn watcher_changes_commit_during_bulk_indexing() {
+        let mut builder = Schema::builder();
+        let path = builder.add_text_field("path", STRING | STORED);
+        let index = Index::create_in_ram(builder.build());
+        let reader = index.reader().unwrap();
+        let writer = WriterHandle::spawn(&index, "test").unwrap();
+        let docs = || {
+            reader.reload().unwrap();
+            reader.searcher().search(&AllQuery, &Count).unwrap()
+        };
+
+        // Las altas masivas solas no se commitean por tiempo
+        writer.add(doc!(path => "/e")).unwrap();
+        thread::sleep(COMMIT_INTERVAL + Duration::from_millis(500));
+        assert_eq!(docs(), 0);
+
+        // Un cambio del watcher se commitea a tiempo aunque la indexación siga
+        writer.upsert(Term::from_field_text(path, "/f"), doc!(path => "/f")).unwrap();
+        thread::sleep(COMMIT_INTERVAL + Duration::from_millis(500));
+        assert_eq!(docs(), 2);
+
+        writer.add(doc!(path => "/g")).unwrap();
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(writer.commit()).unwrap();
+        assert_eq!(docs(), 3);
+    }
+}
@@ -1,22 +1,164 @@
-import { useState, useEffect, useCallback, useRef } from "react";
+import { useState, useEffect, useCallback, useRef, type ReactNode, type UIEvent } from "react";
 import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
 import "./App.css";
 
 interface SearchResult {
   name: string;
   path: string;
   isApp?: boolean;
+  isDir?: boolean;
   icon?: string;
+  matched?: 'name' | 'content';
+  snippet?: string;
+  nameRanges?: [number, number][];
+  pathRanges?: [number, number][];
+  actions: ResultAction[];
+  explain?: Explanation;
 }
 
-type SearchMode = 'apps' | 'files' | 'llm';
+type ResultAction = 'open' | 'reveal' | 'copy_path';
+
+// Desglose del puntaje (explain_search / explain_app_search)
+interface Explanation {
+  base: number;
+  factors: { name: string; multiplier: number }[];
+  score: number;
+}
+
+// Resultado de search_index / app_search (SearchResult en results.rs)
+interface ResultItem {
+  kind: 'file' | 'dir' | 'app';
+  title: string;
+  subtitle: string;
+  path: string;
+  score: number;
+  icon: string | null;
+  matched: 'name' | 'content';
+  snippet: string | null;
+  // Rangos [inicio, fin) en caracteres que matchean la query
+  title_ranges: [number, number][];
+  subtitle_ranges: [number, number][];
+  actions: ResultAction[];
+  explain?: Explanation;
+}
+
+// Lo que devuelve search y lo que llega en los eventos search-results mientras
+// faltan fuentes (SearchUpdate en providers.rs)
+interface SearchUpdate {
+  id: number;
+  results: ResultItem[];
+  pending: string[];
+  failed: { provider: string; error: AlephError }[];
+}
+
+// Error de cualquier comando (AlephError en error.rs)
+interface AlephError {
+  code: 'index_not_ready' | 'bad_query' | 'not_found' | 'invalid_input' | 'busy' | 'timeout'
+    | 'no_home_dir' | 'io' | 'llm' | 'internal';
+  message: string;
+}
+
+const isAlephError = (error: unknown): error is AlephError =>
+  typeof error === 'object' && error !== null && 'code' in error && 'message' in error;
+
+// Qué se le muestra al usuario según el error
+const errorMessage = (error: unknown): string => {
+  if (!isAlephError(error)) return "Something went wrong";
+  switch (error.code) {
+    case 'index_not_ready': return "The index isn't ready yet, try again in a moment";
+    case 'bad_query': return "Couldn't understand the query";
+    case 'not_found': return "It's no longer there";
+    default: return error.message;
+  }
+};
+
+// Trabajo de indexación en background (eventos index-progress / index-ready)
+interface IndexJob {
+  kind: 'index' | 'reconcile' | 'migrate' | 'compact';
+  target: string;
+  started_at: number;
+  scanned: number;
+  indexed: number;
+  current_dir: string | null;
+  eta_secs: number | null;
+}
+
+// 'all' busca en todas las fuentes a la vez con el comando search
+type SearchMode = 'all' | 'apps' | 'files' | 'llm';
+
+// Orden de los resultados (SortMode en ranking.rs)
+type SortMode = 'relevance' | 'modified' | 'size' | 'name';
+
+const SORT_MODES: { value: SortMode; label: string }[] = [
+  { value: 'relevance', label: 'Relevance' },
+  { value: 'modified', label: 'Modified' },
+  { value: 'size', label: 'Size' },
+  { value: 'name', label: 'Name' },
+];
+
+// Resultados por página; al llegar al final de la lista se pide la siguiente
+const PAGE_SIZE = 15;
+// El offset más alto que acepta el backend (MAX_OFFSET en ranking.rs)
+const MAX_OFFSET = 1000;
+
+const jobKey = (job: IndexJob) => `${job.kind}:${job.target}`;
+
+// Función para detectar si un archivo es una aplicación
+const isApplication = (_path: string, name: string): boolean => {
+  const lowerName = name.toLowerCase();
+  
+  // macOS applications
+  if (lowerName.endsWith('.app')) return true;
+  
+  // Common executable extensions
+  const executableExtensions = ['.exe', '.app', '.dmg', '.pkg'];
+  return executableExtensions.some(ext => lowerName.endsWith(ext));
+};
+
+const toSearchResult = (hit: ResultItem): SearchResult => ({
+  name: hit.title,
+  path: hit.path,
+  isApp: hit.kind === 'app' || isApplication(hit.path, hit.title),
+  isDir: hit.kind === 'dir',
+  icon: hit.icon || undefined,
+  matched: hit.matched,
+  snippet: hit.snippet || undefined,
+  nameRanges: hit.title_ranges,
+  pathRanges: hit.subtitle_ranges,
+  actions: hit.actions,
+  explain: hit.explain
+});
+
+// Resalta los rangos que matchean; se cuentan en caracteres (code points), como en el backend
+const highlight = (text: string, ranges?: [number, number][]) => {
+  if (!ranges || ranges.length === 0) return text;
+  const chars = Array.from(text);
+  const parts: ReactNode[] = [];
+  let last = 0;
+  ranges.forEach(([start, end], i) => {
+    if (start >= chars.length) return;
+    if (start > last) parts.push(chars.slice(last, start).join(''));
+    parts.push(<mark key={i} className="match">{chars.slice(start, end).join('')}</mark>);
+    last = Math.min(end, chars.length);
+  });
+  if (last < chars.length) parts.push(chars.slice(last).join(''));
+  return parts;
+};
+
+const formatEta = (secs: number): string =>
+  secs < 60 ? `${secs}s` : `${Math.ceil(secs / 60)} min`;
+
+const formatExplanation = (explain: Explanation) =>
+  [`bm25 ${explain.base.toFixed(2)}`, ...explain.factors.map(f => `${f.name} ×${f.multiplier.toFixed(2)}`)]
+    .join(' · ') + ` = ${explain.score.toFixed(2)}`;
 
 function App() {
   const [query, setQuery] = useState("");
   const [results, setResults] = useState<SearchResult[]>([]);
   const [selectedIndex, setSelectedIndex] = useState(0);
   const [isLoading, setIsLoading] = useState(false);
-  const [searchMode, setSearchMode] = useState<SearchMode>('apps');
+  const [searchMode, setSearchMode] = useState<SearchMode>('all');
   const [indexingStatus, setIndexingStatus] = useState({
     files: 'not_created', // 'not_created' | 'creating' | 'ready' | 'error'
     apps: 'not_created'
@@ -24,16 +166,28 @@ function App() {
   const [llmResponse, setLlmResponse] = useState("");
   const [llmHistory, setLlmHistory] = useState<Array<{query: string, response: string}>>([]);
   const previousQueryRef = useRef("");
+  // Id de la última búsqueda en modo 'all': los avisos y respuestas de otras se ignoran
+  const searchIdRef = useRef(0);
+  const [jobs, setJobs] = useState<Record<string, IndexJob>>({});
+  // Trabajos terminados; cambia con cada index-ready
+  const [finishedJobs, setFinishedJobs] = useState(0);
+  // Oculto (Ctrl/Cmd+Shift+D): muestra por qué cada resultado quedó donde quedó
+  const [explainRanking, setExplainRanking] = useState(false);
+  const [sortMode, setSortMode] = useState<SortMode>('relevance');
+  // Si la última página vino llena puede haber más resultados
+  const [hasMore, setHasMore] = useState(false);
+  const [searchError, setSearchError] = useState<AlephError | null>(null);
+
+  // Mientras haya trabajos que agregan documentos, los resultados pueden estar incompletos
+  const fileJobs = Object.values(jobs).filter(job => job.target !== 'apps' && job.kind !== 'compact');
+  const appJobs = Object.values(jobs).filter(job => job.target === 'apps');
+  const indexedSoFar = fileJobs.reduce((total, job) => total + job.indexed, 0);
 
   // Detectar si el índice existe usando una búsqueda de prueba
   const checkIndexStatus = useCallback(async (indexType: 'apps' | 'files') => {
     try {
       // Intentar una búsqueda vacía para ver si el índice existe
-      if (indexType === 'apps') {
-        await invoke<[string, string, string | null][]>("app_search", { query: "__test_empty__" });
-      } else {
-        await invoke<[string, string, number, string | null][]>("search_index", { query: "__test_empty__" });
-      }
+      await invoke<ResultItem[]>(indexType === 'apps' ? "app_search" : "search_index", { query: "__test_empty__" });
       
       // Si llegamos aquí, el índice existe y está listo
       setIndexingStatus(prev => ({ ...prev, [indexType]: 'ready' }));
@@ -41,23 +195,12 @@ function App() {
       
     } catch (error) {
       // Si falla, puede ser que el índice no existe o se está creando
-      setIndexingStatus(prev => ({ ...prev, [indexType]: 'not_created' }));
-      return 'not_created';
+      const status = isAlephError(error) && error.code === 'index_not_ready' ? 'creating' : 'not_created';
+      setIndexingStatus(prev => ({ ...prev, [indexType]: status }));
+      return status;
     }
   }, []);
 
-  // Función para detectar si un archivo es una aplicación
-  const isApplication = (_path: string, name: string): boolean => {
-    const lowerName = name.toLowerCase();
-    
-    // macOS applications
-    if (lowerName.endsWith('.app')) return true;
-    
-    // Common executable extensions
-    const executableExtensions = ['.exe', '.app', '.dmg', '.pkg'];
-    return executableExtensions.some(ext => lowerName.endsWith(ext));
-  };
-
   // Función para manejar consultas LLM
   const handleLlmQuery = useCallback(async (searchQuery: string) => {
     if (!searchQuery.trim()) {
@@ -81,9 +224,13 @@ function App() {
   }, []);
 
   // Función de búsqueda que deja que Rust maneje la creación de índices automáticamente
-  const searchFiles = useCallback(async (searchQuery: string, shouldResetSelection = false) => {
+  const searchFiles = useCallback(async (searchQuery: string, shouldResetSelection = false, offset = 0) => {
     if (!searchQuery.trim()) {
+      // Lo que siga llegando de una búsqueda anterior ya no sirve
+      searchIdRef.current++;
       setResults([]);
+      setHasMore(false);
+      setSearchError(null);
       setSelectedIndex(0);
       if (searchMode === 'llm') {
         setLlmResponse("");
@@ -107,36 +254,40 @@ function App() {
         setIndexingStatus(prev => ({ ...prev, [indexType]: 'creating' }));
       }
 
-      let formattedResults: SearchResult[] = [];
-
-      if (searchMode === 'apps') {
-        const searchResults = await invoke<[string, string, string | null][]>("app_search", { 
-          query: searchQuery 
+      if (searchMode === 'all') {
+        // Todas las fuentes a la vez; lo parcial llega por el evento search-results
+        const id = ++searchIdRef.current;
+        const update = await invoke<SearchUpdate>("search", {
+          id,
+          query: searchQuery,
+          limit: PAGE_SIZE,
+          explain: explainRanking
         });
-        formattedResults = searchResults.map(([name, path, icon]) => ({
-          name,
-          path,
-          isApp: true,
-          icon: icon || undefined
-        }));
-      } else {
-        const searchResults = await invoke<[string, string, number, string | null][]>("search_index", { 
-          query: searchQuery 
-        });
-        formattedResults = searchResults.map(([name, path, _score, icon]) => ({
-          name,
-          path,
-          isApp: isApplication(path, name),
-          icon: icon || undefined
-        }));
+        if (id !== searchIdRef.current) return;
+        const merged = update.results.map(toSearchResult);
+        setResults(merged);
+        setHasMore(false);
+        // Si alguna fuente falló pero hay resultados, se muestran igual
+        setSearchError(merged.length === 0 && update.failed.length > 0 ? update.failed[0].error : null);
+        setIndexingStatus(prev => ({ ...prev, files: 'ready' }));
+        setSelectedIndex(prev => shouldResetSelection || prev >= merged.length ? 0 : prev);
+        return;
       }
+
+      const page = { limit: PAGE_SIZE, offset, sort: sortMode };
+      const command = searchMode === 'apps'
+        ? (explainRanking ? "explain_app_search" : "app_search")
+        : (explainRanking ? "explain_search" : "search_index");
+      const searchResults = await invoke<ResultItem[]>(command, { query: searchQuery, ...page });
+      const formattedResults = searchResults.map(toSearchResult);
       
       // Si llegamos aquí, el índice está listo
       setIndexingStatus(prev => ({ ...prev, [indexType]: 'ready' }));
+      setSearchError(null);
 
       // Para el modo apps, todos los resultados son apps
-      // Para files, priorizar aplicaciones si las hay
-      const sortedResults = searchMode === 'apps' 
+      // Para files, priorizar aplicaciones si las hay (solo ordenando por relevancia)
+      const sortedResults = searchMode === 'apps' || sortMode !== 'relevance'
         ? formattedResults 
         : formattedResults.sort((a, b) => {
             if (a.isApp && !b.isApp) return -1;
@@ -144,18 +295,37 @@ function App() {
             return 0;
           });
 
+      setHasMore(sortedResults.length === PAGE_SIZE && offset + PAGE_SIZE <= MAX_OFFSET);
+      if (offset > 0) {
+        // Página siguiente: se agrega al final sin mover la selección
+        setResults(prev => [...prev, ...sortedResults]);
+        return;
+      }
       setResults(sortedResults);
       // Solo resetear selectedIndex si se solicita explícitamente o si el índice actual está fuera del rango
       setSelectedIndex(prev => shouldResetSelection || prev >= sortedResults.length ? 0 : prev);
     } catch (error) {
       console.error("Error searching:", error);
       const indexType = searchMode === 'apps' ? 'apps' : 'files';
-      setIndexingStatus(prev => ({ ...prev, [indexType]: 'error' }));
+      // Una query inválida no dice nada del índice; uno que no está listo se sigue armando
+      if (!isAlephError(error) || error.code !== 'bad_query') {
+        const status = isAlephError(error) && error.code === 'index_not_ready' ? 'creating' : 'error';
+        setIndexingStatus(prev => ({ ...prev, [indexType]: status }));
+      }
+      setSearchError(isAlephError(error) ? error : { code: 'internal', message: String(error) });
       setResults([]);
+      setHasMore(false);
     } finally {
       setIsLoading(false);
     }
-  }, [searchMode, indexingStatus, handleLlmQuery]);
+  }, [searchMode, indexingStatus, handleLlmQuery, explainRanking, sortMode]);
+
+  // Pide la página siguiente cuando el scroll llega cerca del final
+  const handleResultsScroll = (e: UIEvent<HTMLDivElement>) => {
+    const el = e.currentTarget;
+    if (!hasMore || isLoading || el.scrollTop + el.clientHeight < el.scrollHeight - 40) return;
+    searchFiles(query, false, results.length);
+  };
 
   // Debounce para la búsqueda (solo para apps y files, no para LLM)
   useEffect(() => {
@@ -175,6 +345,17 @@ function App() {
     return () => clearTimeout(timeoutId);
   }, [query, searchFiles, searchMode]);
 
+  // Una búsqueda que encontró el índice armándose por primera vez se repite al terminar.
+  // Se lee lo último por ref: solo tiene que correr cuando termina un trabajo
+  const latestSearchRef = useRef({ query, searchFiles, searchError });
+  latestSearchRef.current = { query, searchFiles, searchError };
+  useEffect(() => {
+    const latest = latestSearchRef.current;
+    if (finishedJobs > 0 && latest.searchError?.code === 'index_not_ready') {
+      latest.searchFiles(latest.query);
+    }
+  }, [finishedJobs]);
+
   // Verificar al inicio qué índices ya están creados
   useEffect(() => {
     const checkInitialIndexStatus = async () => {
@@ -187,18 +368,62 @@ function App() {
     checkInitialIndexStatus();
   }, [checkIndexStatus]);
 
+  // Resultados parciales de la búsqueda en curso (modo 'all')
+  useEffect(() => {
+    const unlisten = listen<SearchUpdate>("search-results", (event) => {
+      if (event.payload.id !== searchIdRef.current) return;
+      setResults(event.payload.results.map(toSearchResult));
+    });
+    return () => {
+      unlisten.then(fn => fn());
+    };
+  }, []);
+
+  // Avance de la indexación en background
+  useEffect(() => {
+    const unlisteners = [
+      listen<IndexJob>("index-progress", (event) => {
+        setJobs(prev => ({ ...prev, [jobKey(event.payload)]: event.payload }));
+      }),
+      listen<IndexJob>("index-ready", (event) => {
+        setJobs(prev => {
+          const next = { ...prev };
+          delete next[jobKey(event.payload)];
+          return next;
+        });
+        setFinishedJobs(n => n + 1);
+      }),
+    ];
+    return () => {
+      unlisteners.forEach(unlisten => unlisten.then(fn => fn()));
+    };
+  }, []);
+
   // Limpiar resultados cuando cambia el modo de búsqueda
   useEffect(() => {
     setQuery("");
     setResults([]);
     setSelectedIndex(0);
     setLlmResponse("");
+    setSearchError(null);
     previousQueryRef.current = "";
   }, [searchMode]);
 
   // Manejar navegación con teclado
   useEffect(() => {
     const handleKeyDown = (e: KeyboardEvent) => {
+      if ((e.ctrlKey || e.metaKey) && e.shiftKey && e.key.toLowerCase() === 'd') {
+        e.preventDefault();
+        setExplainRanking(prev => !prev);
+        return;
+      }
+      // Ctrl/Cmd+Shift+C copia el path del resultado seleccionado
+      const selected = searchMode !== 'llm' ? results[selectedIndex] : undefined;
+      if ((e.ctrlKey || e.metaKey) && e.shiftKey && e.key.toLowerCase() === 'c' && selected?.actions.includes('copy_path')) {
+        e.preventDefault();
+        navigator.clipboard.writeText(selected.path);
+        return;
+      }
       switch (e.key) {
         case 'ArrowDown':
           if (results.length === 0 || searchMode === 'llm') return;
@@ -221,8 +446,11 @@ function App() {
             if (query.trim()) {
               handleLlmQuery(query);
             }
-          } else if (results[selectedIndex]) {
-            openItem(results[selectedIndex]);
+          } else if (selected && (e.ctrlKey || e.metaKey) && selected.actions.includes('reveal')) {
+            // Ctrl/Cmd+Enter abre la carpeta que lo contiene
+            revealItem(selected);
+          } else if (selected) {
+            openItem(selected);
           }
           break;
         case 'Escape':
@@ -240,12 +468,23 @@ function App() {
   // Función para abrir archivo/aplicación
   const openItem = async (item: SearchResult) => {
     try {
-      await invoke("open_path", { path: item.path });
+      // Con la query, para recordar qué se eligió al buscarla
+      await invoke("open_path", { path: item.path, query });
       // Limpiar búsqueda después de abrir
       setQuery("");
       setResults([]);
     } catch (error) {
       console.error("Error opening item:", error);
+      setSearchError(isAlephError(error) ? error : { code: 'internal', message: String(error) });
+    }
+  };
+
+  const revealItem = async (item: SearchResult) => {
+    try {
+      await invoke("reveal_path", { path: item.path });
+    } catch (error) {
+      console.error("Error revealing item:", error);
+      setSearchError(isAlephError(error) ? error : { code: 'internal', message: String(error) });
     }
   };
 
@@ -266,6 +505,7 @@ function App() {
     
     // Fallback to emoji icons
     if (item.isApp) return "🚀";
+    if (item.isDir) return "📂";
     
     const extension = item.name.split('.').pop()?.toLowerCase();
     switch (extension) {
@@ -286,6 +526,12 @@ function App() {
         <div className="search-header">
           {/* Mode Selector */}
           <div className="mode-selector">
+            <button
+              className={`mode-button ${searchMode === 'all' ? 'active' : ''}`}
+              onClick={() => setSearchMode('all')}
+            >
+              🔎 All
+            </button>
             <button
               className={`mode-button ${searchMode === 'apps' ? 'active' : ''}`}
               onClick={() => setSearchMode('apps')}
@@ -313,7 +559,9 @@ function App() {
               value={query}
               onChange={(e) => setQuery(e.target.value)}
               placeholder={
-                searchMode === 'apps' 
+                searchMode === 'all'
+                  ? "Search apps and files..."
+                  : searchMode === 'apps' 
                   ? (indexingStatus.apps === 'creating' ? "Creating app index..." : "Search applications...")
                   : searchMode === 'files'
                   ? (indexingStatus.files === 'creating' ? "Creating file index..." : "Search files...")
@@ -322,14 +570,39 @@ function App() {
               className="search-input"
               autoFocus
             />
+            {(searchMode === 'apps' || searchMode === 'files') && (
+              <select
+                className="sort-select"
+                value={sortMode}
+                onChange={(e) => setSortMode(e.target.value as SortMode)}
+                title="Sort results"
+              >
+                {SORT_MODES.map(mode => (
+                  <option key={mode.value} value={mode.value}>{mode.label}</option>
+                ))}
+              </select>
+            )}
           </div>
           
 
         </div>
 
         <div className="search-content">
+          {(searchMode === 'files' || searchMode === 'all') && fileJobs.length > 0 && (
+            <p className="background-indexing-hint">
+              ⏳ Indexing {fileJobs[0].current_dir ?? fileJobs[0].target}… results may be incomplete
+              {fileJobs[0].eta_secs !== null
+                ? ` (~${formatEta(fileJobs[0].eta_secs)} left)`
+                : ` (${fileJobs[0].indexed} indexed so far)`}
+            </p>
+          )}
+
+          {searchMode !== 'llm' && searchError && (
+            <p className={`search-error ${searchError.code}`}>{errorMessage(searchError)}</p>
+          )}
+
           {searchMode !== 'llm' && results.length > 0 && (
-            <div className="results-container">
+            <div className="results-container" onScroll={handleResultsScroll}>
               {results.map((item, index) => (
                 <div
                   key={`${item.path}-${index}`}
@@ -339,8 +612,14 @@ function App() {
                 >
                   <div className="item-icon">{getItemIcon(item)}</div>
                   <div className="item-info">
-                    <div className="item-name">{getDisplayName(item)}</div>
-                    <div className="item-path">{item.path}</div>
+                    <div className="item-name">{highlight(getDisplayName(item), item.nameRanges)}</div>
+                    <div className="item-path">{highlight(item.path, item.pathRanges)}</div>
+                    {item.matched === 'content' && item.snippet && (
+                      <div className="item-snippet">…{item.snippet}…</div>
+                    )}
+                    {explainRanking && item.explain && (
+                      <div className="item-explain">{formatExplanation(item.explain)}</div>
+                    )}
                   </div>
                   {item.isApp && <span className="app-badge">APP</span>}
                 </div>
@@ -447,11 +726,13 @@ function App() {
           </p>
           <div className="indexing-status">
             <span className={`status-badge ${
+              appJobs.length > 0 ? 'indexing' :
               indexingStatus.apps === 'ready' ? 'ready' : 
               indexingStatus.apps === 'creating' ? 'indexing' : 
               'not-created'
             }`}>
               🚀 Apps: {
+                appJobs.length > 0 ? 'Indexing...' :
                 indexingStatus.apps === 'ready' ? 'Ready' :
                 indexingStatus.apps === 'creating' ? 'Creating...' :
                 indexingStatus.apps === 'error' ? 'Error' :
@@ -459,11 +740,13 @@ function App() {
               }
             </span>
             <span className={`status-badge ${
+              fileJobs.length > 0 ? 'indexing' :
               indexingStatus.files === 'ready' ? 'ready' : 
               indexingStatus.files === 'creating' ? 'indexing' : 
               'not-created'
             }`}>
               📁 Files: {
+                fileJobs.length > 0 ? `Indexing (${indexedSoFar})...` :
                 indexingStatus.files === 'ready' ? 'Ready' :
                 indexingStatus.files === 'creating' ? 'Creating...' :
                 indexingStatus.files === 'error' ? 'Error' :